@@ -34,7 +34,7 @@ function downloadSvg() {
 
     Promise.all(promises).then(() => {
       const serializer = new XMLSerializer();
-      const source = serializer.serializeToString(svg);
+      const source = serializer.serializeToString(withTitle(svg));
       const blob = new Blob([source], { type: "image/svg+xml;charset=utf-8" });
       const url = URL.createObjectURL(blob);
       const a = document.createElement("a");
@@ -48,6 +48,17 @@ function downloadSvg() {
   }
 }
 
+// Files have no aria-label to go by, so the name of the flag becomes the
+// <title> of the SVG, next to the <desc> it already has
+function withTitle(svg) {
+  const copy = svg.cloneNode(true);
+  const title = document.createElementNS("http://www.w3.org/2000/svg", "title");
+  title.textContent = svg.getAttribute("aria-label") || "Flag";
+  copy.insertBefore(title, copy.firstChild);
+  copy.removeAttribute("aria-describedby");
+  return copy;
+}
+
 function downloadPng() {
   const svg = document.getElementById("result");
   if (!svg) {
@@ -117,3 +128,16 @@ function scrollTop() {
     behavior: "smooth",
   });
 }
+
+// Called from the app with the bytes of generated files, like palettes
+function downloadFile(filename, type, bytes) {
+  const blob = new Blob([new Uint8Array(bytes)], { type });
+  const url = URL.createObjectURL(blob);
+  const a = document.createElement("a");
+  a.href = url;
+  a.download = filename;
+  document.body.appendChild(a);
+  a.click();
+  document.body.removeChild(a);
+  URL.revokeObjectURL(url);
+}
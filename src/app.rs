@@ -5,66 +5,263 @@ use rand::seq::IndexedRandom;
 use std::ops::Deref;
 
 use crate::FLAGS;
-use crate::flag::Flag;
-use crate::flag_lib::FlagData;
+use crate::flag::{Flag, MixTraceOverlay};
+use crate::flag_lib::{
+    Alignment, Color, ContrastReport, Decomposition, Filter, FlagData, MixMode, MixOptions,
+    NameTemplate, Operation, Palette, PaletteFormat, Parity, Recipe, RenderOptions, Vision,
+};
+use crate::reverse::ReverseMixer;
+
+const MAX_SLOTS: usize = 4;
 
 #[component]
 pub fn App() -> Element {
-    let mut flag1 = use_signal::<Option<FlagData>>(|| None);
-    let mut flag2 = use_signal::<Option<FlagData>>(|| None);
-    let flag_mix = if let (Some(f1), Some(f2)) = (&*flag1.read(), &*flag2.read()) {
-        Some(FlagData::mix(f1, f2))
-    } else {
-        None
+    let mut option_mode = use_signal::<MixMode>(MixMode::default);
+    let mut option_ratio = use_signal::<u32>(|| 50);
+    let mut option_alignment = use_signal::<Alignment>(Alignment::default);
+    let mut option_parity = use_signal::<Parity>(Parity::default);
+    let mut option_naming = use_signal::<NameTemplate>(NameTemplate::default);
+    let mix_options = move || MixOptions {
+        mode: *option_mode.read(),
+        alignment: *option_alignment.read(),
+        parity: *option_parity.read(),
+        naming: *option_naming.read(),
+        ratio: *option_ratio.read(),
+    };
+    let mut slots = use_signal::<Vec<Option<FlagData>>>(|| vec![None, None]);
+    // Edits made to the mix, replayed whenever it changes. Stripes are counted
+    // on the normalized mix, the way they are drawn.
+    let mut ops = use_signal::<Vec<Operation>>(Vec::new);
+    let traced_mix = slots
+        .read()
+        .iter()
+        .cloned()
+        .collect::<Option<Vec<FlagData>>>()
+        .map(|flags| FlagData::mix_traced(&flags, &mix_options()));
+    let flag_mix = traced_mix
+        .as_ref()
+        .map(|(mix, _)| Operation::apply_all(mix, &ops.read()));
+    // Annotations only line up with the mix as long as it hasn't been edited
+    let mut option_explain = use_signal::<bool>(|| false);
+    // Mix before any edits, cells are picked on it
+    let base_mix = traced_mix.as_ref().map(|(mix, _)| mix.clone());
+    let explained_mix = traced_mix.filter(|_| *option_explain.read() && ops.read().is_empty());
+    let stripe_count = flag_mix.as_ref().map_or(0, |flag| flag.lines.len());
+
+    // Stripe and color the stripe edits apply to, counted from 1 in the toolbar
+    let mut edit_stripe = use_signal::<usize>(|| 1);
+    let mut edit_color = use_signal::<Color>(|| Color::WHITE);
+    // Edits need a mix to apply to, they would land on the next one otherwise
+    let has_mix = flag_mix.is_some();
+    let mut push_op = move |op: Operation| {
+        if has_mix {
+            ops.write().push(op);
+        }
+    };
+    let can_turn = flag_mix
+        .as_ref()
+        .is_some_and(|flag| Operation::Mirror.applies_to(flag));
+    // Mix options change the stripes, so edits of single stripes no longer
+    // point at the right ones
+    let mut drop_indexed_ops = move || ops.write().retain(|op| !op.is_indexed());
+    let edit_index = move || edit_stripe.read().clamp(1, stripe_count.max(1)) - 1;
+    // One past the last stripe inserts at the end
+    let insert_index = move || edit_stripe.read().clamp(1, stripe_count + 1) - 1;
+
+    // Clicking a stripe of the result picks it, its color is then overridden
+    // by an operation so it survives any change of render options. Changing
+    // the mix options drops it along with the other single-stripe edits.
+    let mut option_pick = use_signal::<bool>(|| false);
+    // The pick only holds for the mix it was made on, not after the flags,
+    // their order or the mix options change
+    let mut picked = use_signal::<Option<(FlagData, usize, usize)>>(|| None);
+    let picked_color = picked.read().as_ref().and_then(|(mix, index, column)| {
+        let (index, column) = (*index, *column);
+        if base_mix.as_ref() != Some(mix) {
+            return None;
+        }
+        let color = flag_mix.as_ref()?.lines.get(index)?.get(column).copied()?;
+        Some((index, column, color))
+    });
+    let mut override_color = move |index: usize, column: usize, color: Color| {
+        let mut ops = ops.write();
+        // Dragging the picker keeps updating the same override
+        if let Some(Operation::RecolorCell {
+            index: last_index,
+            column: last_column,
+            color: last_color,
+        }) = ops.last_mut()
+            && *last_index == index
+            && *last_column == column
+        {
+            *last_color = color;
+        } else {
+            ops.push(Operation::RecolorCell {
+                index,
+                column,
+                color,
+            });
+        }
     };
 
-    let mut selected_slot = use_signal::<usize>(|| 1);
+    let mut selected_slot = use_signal::<usize>(|| 0);
+    // Mix of every other filled slot with the chosen options, used for
+    // compatibility checks in the chooser
     let get_unselected_flag = move || {
-        if *selected_slot.read() == 2 {
-            flag1.read().clone()
+        let selected = *selected_slot.read();
+        let others: Vec<FlagData> = slots
+            .read()
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != selected)
+            .filter_map(|(_, flag)| flag.clone())
+            .collect();
+        if others.is_empty() {
+            None
         } else {
-            flag2.read().clone()
+            Some(FlagData::mix_many(&others, &mix_options()))
         }
     };
 
+    // Edits point at stripes by index, so they are dropped along with the
+    // flags they were made on
     let swap_flags = move |_| {
-        let (f1, f2) = (flag1.read().clone(), flag2.read().clone());
-        flag1.set(f2);
-        flag2.set(f1);
+        slots.write().reverse();
+        ops.write().clear();
+    };
+
+    let add_slot = move |_| {
+        if slots.read().len() < MAX_SLOTS {
+            slots.write().push(None);
+            selected_slot.set(slots.read().len() - 1);
+            ops.write().clear();
+        }
+    };
+
+    let mut remove_slot = move |i: usize| {
+        if slots.read().len() > 2 {
+            slots.write().remove(i);
+            selected_slot.set(0);
+            ops.write().clear();
+        }
     };
 
     let mut option_icons = use_signal::<bool>(|| true);
-    let mut option_reduce_strain = use_signal::<bool>(|| false);
+    let mut option_filters = use_signal::<Vec<Filter>>(Vec::new);
+    let mut new_filter = use_signal::<usize>(|| 0);
     let mut option_softness = use_signal::<f32>(|| 40.0);
     let mut option_blur = use_signal::<f32>(|| 0.0);
+    let mut option_vision = use_signal::<Vision>(Vision::default);
 
-    let mut select_flag = move |flag: FlagData| {
-        let slot = *selected_slot.read();
-        if slot == 1 {
-            flag1.set(Some(flag));
-            if (*flag2.read()).is_none() {
-                selected_slot.set(2)
+    let render_options = move || RenderOptions {
+        icons: *option_icons.read(),
+        filters: option_filters.read().clone(),
+        softness: *option_softness.read(),
+        blur: *option_blur.read(),
+        vision: *option_vision.read(),
+    };
+    let contrast = flag_mix
+        .as_ref()
+        .map(|flag| ContrastReport::new(flag, &render_options()));
+
+    let mut palette_format = use_signal::<PaletteFormat>(PaletteFormat::default);
+    let download_palette = {
+        let flag_mix = flag_mix.clone();
+        move |_| {
+            if let Some(flag) = &flag_mix {
+                let palette = Palette::new(flag);
+                let format = *palette_format.read();
+                let file = format!("{}.{}", palette.slug(), format.extension());
+                let args = serde_json::json!([file, format.mime(), palette.export(format)]);
+                document::eval(&format!("downloadFile(...{args})"));
             }
-        } else if slot == 2 {
-            flag2.set(Some(flag));
-            if (*flag1.read()).is_none() {
-                selected_slot.set(1)
+        }
+    };
+
+    let mut recipe_text = use_signal::<String>(String::new);
+    let mut recipe_error = use_signal::<Option<String>>(|| None);
+
+    let save_recipe = move |_| {
+        if let Some(flags) = slots
+            .read()
+            .iter()
+            .cloned()
+            .collect::<Option<Vec<FlagData>>>()
+        {
+            let recipe = Recipe::new(&flags, mix_options(), ops.read().clone(), render_options());
+            recipe_text.set(recipe.to_json());
+            recipe_error.set(None);
+        }
+    };
+
+    let load_recipe = move |_| {
+        let loaded = Recipe::from_json(&recipe_text.read())
+            .and_then(|recipe| Ok((recipe.sources(&FLAGS)?, recipe)));
+        match loaded {
+            Ok((flags, recipe)) => {
+                slots.set(flags.into_iter().map(Some).collect());
+                selected_slot.set(0);
+                option_mode.set(recipe.mix.mode);
+                option_alignment.set(recipe.mix.alignment);
+                option_parity.set(recipe.mix.parity);
+                option_naming.set(recipe.mix.naming);
+                option_ratio.set(recipe.mix.ratio);
+                ops.set(recipe.ops);
+                option_icons.set(recipe.render.icons);
+                option_filters.set(recipe.render.filters);
+                option_softness.set(recipe.render.softness);
+                option_blur.set(recipe.render.blur);
+                option_vision.set(recipe.render.vision);
+                recipe_error.set(None);
             }
+            Err(e) => recipe_error.set(Some(e.to_string())),
+        }
+    };
+
+    let mut select_flag = move |flag: FlagData| {
+        let slot = *selected_slot.read();
+        slots.write()[slot] = Some(flag);
+        ops.write().clear();
+        if let Some(empty) = slots.read().iter().position(Option::is_none) {
+            selected_slot.set(empty);
         }
     };
 
     let select_random_flags = move |_| {
         let mut rng = rng();
         let flags = FLAGS.deref().clone();
-        let flag_a = flags.choose(&mut rng).unwrap().clone();
-        let mut flag_b = flags.choose(&mut rng).unwrap().clone();
+        let count = slots.read().len();
+        let mut chosen: Vec<FlagData> = vec![flags.choose(&mut rng).unwrap().clone()];
 
-        while flag_a.name == flag_b.name || !FlagData::is_compatible(&flag_a, &flag_b) {
-            flag_b = flags.choose(&mut rng).unwrap().clone();
+        while chosen.len() < count {
+            let flag = flags.choose(&mut rng).unwrap().clone();
+            if chosen.iter().all(|other| {
+                other.id != flag.id
+                    && FlagData::is_compatible(
+                        other,
+                        &flag,
+                        *option_alignment.read(),
+                        *option_parity.read(),
+                    )
+            }) {
+                chosen.push(flag);
+            }
         }
 
-        flag1.set(Some(flag_a));
-        flag2.set(Some(flag_b));
+        slots.set(chosen.into_iter().map(Some).collect());
+        ops.write().clear();
+    };
+
+    // The reverse mixer replaces the flag chooser
+    let mut reverse_page = use_signal::<bool>(|| false);
+    let select_decomposition = move |found: Decomposition| {
+        slots.set(found.sources.into_iter().map(Some).collect());
+        selected_slot.set(0);
+        option_mode.set(found.options.mode);
+        option_alignment.set(found.options.alignment);
+        option_parity.set(found.options.parity);
+        ops.write().clear();
+        reverse_page.set(false);
     };
 
     let mut query = use_signal::<String>(String::new);
@@ -115,37 +312,36 @@ pub fn App() -> Element {
             }
             div {
                 class: "slot-row",
-                div {
-                    class: if *selected_slot.read() == 1 { "flag-slot selected" } else {"flag-slot" },
-                    onclick: move |_| selected_slot.set(1),
-                    if let Some(flag) = &*flag1.read() {
-                        Flag {
-                            key: "f1-{option_icons}",
-                            flag: flag.clone(),
-                            id: "f1".to_string(),
-                            option_icons: *option_icons.read(),
-                            option_reduce_strain: *option_reduce_strain.read(),
-                            option_blur: *option_blur.read(),
-                            option_softness: *option_softness.read()
-                        }
-                        p { class: "flag-slot-name", {flag.name.to_string()} }
+                for (i, slot) in slots.read().iter().enumerate() {
+                    if i > 0 {
+                        p { class: "flag-slot-op", "+" }
                     }
-                }
-                p { class: "flag-slot-op", "+" }
-                div {
-                    class: if *selected_slot.read() == 2 { "flag-slot selected" } else {"flag-slot" },
-                    onclick: move |_| selected_slot.set(2),
-                    if let Some(flag) = &*flag2.read() {
-                        Flag {
-                            key: "f2-{option_icons}",
-                            flag: flag.clone(),
-                            id: "f2".to_string(),
-                            option_icons: *option_icons.read(),
-                            option_reduce_strain: *option_reduce_strain.read(),
-                            option_blur: *option_blur.read(),
-                            option_softness: *option_softness.read()
+                    div {
+                        class: if *selected_slot.read() == i { "flag-slot selected" } else {"flag-slot" },
+                        onclick: move |_| selected_slot.set(i),
+                        if let Some(flag) = slot {
+                            Flag {
+                                key: "f{i}-{option_icons}",
+                                flag: flag.clone(),
+                                id: format!("f{}", i + 1),
+                                option_icons: *option_icons.read(),
+                                option_filters: option_filters.read().clone(),
+                                option_blur: *option_blur.read(),
+                                option_softness: *option_softness.read(),
+                                option_vision: *option_vision.read()
+                            }
+                            p { class: "flag-slot-name", {flag.name.to_string()} }
+                        }
+                        if slots.read().len() > 2 {
+                            button {
+                                class: "flag-slot-remove",
+                                onclick: move |e| {
+                                    e.stop_propagation();
+                                    remove_slot(i);
+                                },
+                                "✕"
+                            }
                         }
-                        p { class: "flag-slot-name", {flag.name.to_string()} }
                     }
                 }
                 p { class: "flag-slot-op", "=" }
@@ -157,14 +353,74 @@ pub fn App() -> Element {
                             flag: flag.clone(),
                             id: "result".to_string(),
                             option_icons: *option_icons.read(),
-                            option_reduce_strain: *option_reduce_strain.read(),
+                            option_filters: option_filters.read().clone(),
                             option_blur: *option_blur.read(),
-                            option_softness: *option_softness.read()
+                            option_softness: *option_softness.read(),
+                            option_vision: *option_vision.read(),
+                            onpick: option_pick.read().then_some(EventHandler::new({
+                                let base_mix = base_mix.clone();
+                                move |(index, column)| picked.set(base_mix.clone().map(|mix| (mix, index, column)))
+                            })),
                         },
+                        if let Some((mix, trace)) = &explained_mix {
+                            MixTraceOverlay { flag: mix.clone(), trace: trace.clone() }
+                        }
                         p { {flag.name.to_string()} }
                     }
                 }
             }
+            if let Some((_, trace)) = &explained_mix {
+                ul {
+                    class: "mix-trace-notes",
+                    for note in trace.describe() {
+                        li { {note} }
+                    }
+                }
+            }
+            if let Some((index, column, color)) = picked_color {
+                div {
+                    class: "input-row edit-row color-override",
+                    span {
+                        if flag_mix.as_ref().is_some_and(|flag| flag.lines[index].len() > 1) {
+                            "Stripe {index + 1} of {(b'A' + column as u8) as char}"
+                        } else {
+                            "Stripe {index + 1}"
+                        }
+                    }
+                    input {
+                        type: "color",
+                        title: "Stripe color",
+                        value: "{color}",
+                        oninput: move |e| {
+                            if let Ok(color) = e.value().parse() {
+                                override_color(index, column, color);
+                            }
+                        },
+                    }
+                    button {
+                        onclick: move |_| picked.set(None),
+                        "Done"
+                    }
+                }
+            }
+            if let Some(report) = &contrast {
+                div {
+                    class: "contrast-report",
+                    if let Some(lowest) = report.contrasts.first() && !lowest.is_low() {
+                        p { "Every boundary has enough contrast (closest: {lowest.describe(report.columns)})" }
+                    }
+                    for (i, warning) in report.warnings().enumerate() {
+                        p {
+                            key: "{i}",
+                            class: "contrast-warning",
+                            for color in warning.colors {
+                                span { class: "swatch", background_color: "{color}" }
+                            }
+                            "⚠ Hard to tell apart: {warning.describe(report.columns)}"
+                        }
+                    }
+                }
+            }
             div {
                 class: "input-row",
                 button {
@@ -172,6 +428,18 @@ pub fn App() -> Element {
                     img { src: get_asset!("dice.svg") },
                     "I'm Feeling Lucky",
                 }
+                button {
+                    class: if slots.read().len() >= MAX_SLOTS { "disabled" } else { "" },
+                    onclick: add_slot,
+                    "+ Add Flag",
+                }
+                button {
+                    onclick: move |_| {
+                        let current = *reverse_page.read();
+                        reverse_page.set(!current);
+                    },
+                    if *reverse_page.read() { "Back to Kitchen" } else { "Reverse Mixer" }
+                }
                 button {
                     class: if flag_mix.is_none() { "disabled" } else { "" },
                     onclick: swap_flags,
@@ -190,8 +458,185 @@ pub fn App() -> Element {
                     img { src: get_asset!("download.svg") },
                     "Download SVG"
                 },
+                select {
+                    class: "palette-select",
+                    title: "Palette format",
+                    onchange: move |e| {
+                        if let Some(format) = PaletteFormat::ALL.iter().find(|format| format.label() == e.value()) {
+                            palette_format.set(*format);
+                        }
+                    },
+                    for format in PaletteFormat::ALL {
+                        option {
+                            value: format.label(),
+                            selected: *palette_format.read() == format,
+                            {format.label()}
+                        }
+                    }
+                }
+                button {
+                    class: if flag_mix.is_none() { "disabled" } else { "" },
+                    onclick: download_palette,
+                    img { src: get_asset!("download.svg") },
+                    "Download Palette"
+                },
             },
+            div {
+                class: "input-row edit-row",
+                button {
+                    class: if flag_mix.is_none() { "disabled" } else { "" },
+                    onclick: move |_| push_op(Operation::Reverse),
+                    "Reverse"
+                }
+                button {
+                    class: if !can_turn { "disabled" } else { "" },
+                    disabled: !can_turn,
+                    onclick: move |_| push_op(Operation::Mirror),
+                    "Mirror"
+                }
+                button {
+                    class: if !can_turn { "disabled" } else { "" },
+                    disabled: !can_turn,
+                    onclick: move |_| push_op(Operation::Rotate),
+                    "Rotate"
+                }
+                input {
+                    type: "number",
+                    class: "stripe-input",
+                    min: 1,
+                    max: stripe_count + 1,
+                    title: "Stripe",
+                    value: *edit_stripe.read(),
+                    oninput: move |e| {
+                        if let Ok(stripe) = e.value().parse() {
+                            edit_stripe.set(stripe);
+                        }
+                    },
+                }
+                input {
+                    type: "color",
+                    title: "Stripe color",
+                    value: "{edit_color}",
+                    oninput: move |e| {
+                        if let Ok(color) = e.value().parse() {
+                            edit_color.set(color);
+                        }
+                    },
+                }
+                button {
+                    class: if flag_mix.is_none() { "disabled" } else { "" },
+                    onclick: move |_| push_op(Operation::InsertStripe {
+                        index: insert_index(),
+                        color: *edit_color.read(),
+                    }),
+                    "Insert"
+                }
+                button {
+                    class: if stripe_count < 2 { "disabled" } else { "" },
+                    onclick: move |_| push_op(Operation::RemoveStripe { index: edit_index() }),
+                    "Remove"
+                }
+                button {
+                    class: if flag_mix.is_none() { "disabled" } else { "" },
+                    onclick: move |_| push_op(Operation::RecolorStripe {
+                        index: edit_index(),
+                        color: *edit_color.read(),
+                    }),
+                    "Recolor"
+                }
+                button {
+                    class: if flag_mix.is_none() { "disabled" } else { "" },
+                    onclick: move |_| {
+                        let current = *option_pick.read();
+                        option_pick.set(!current);
+                        picked.set(None);
+                    },
+                    if *option_pick.read() { "Stop Picking" } else { "Pick Colors" }
+                }
+                button {
+                    class: if ops.read().is_empty() { "disabled" } else { "" },
+                    onclick: move |_| ops.write().clear(),
+                    "Reset"
+                }
+            }
+            div {
+                class: "input-row edit-row filter-row",
+                select {
+                    title: "Filter",
+                    onchange: move |e| {
+                        if let Some(i) = Filter::ALL.iter().position(|filter| filter.label() == e.value()) {
+                            new_filter.set(i);
+                        }
+                    },
+                    for (i, filter) in Filter::ALL.iter().enumerate() {
+                        option {
+                            value: filter.label(),
+                            selected: *new_filter.read() == i,
+                            {filter.label()}
+                        }
+                    }
+                }
+                button {
+                    onclick: move |_| {
+                        let filter = Filter::ALL[*new_filter.read()];
+                        option_filters.write().push(filter);
+                    },
+                    "Add Filter"
+                }
+                for (i, filter) in option_filters.read().iter().enumerate() {
+                    div {
+                        key: "{i}-{filter.label()}",
+                        class: "filter",
+                        span { {filter.label()} }
+                        if let Some((value, max)) = filter.parameter() {
+                            input {
+                                type: "range",
+                                min: 0.0,
+                                max,
+                                step: max / 20.,
+                                value,
+                                onchange: move |e| {
+                                    if let Ok(value) = e.value().parse() {
+                                        option_filters.write()[i].set_parameter(value);
+                                    }
+                                },
+                            }
+                        }
+                        button {
+                            onclick: move |_| {
+                                option_filters.write().remove(i);
+                            },
+                            "✕"
+                        }
+                    }
+                }
+            }
+            div {
+                class: "recipe-row",
+                textarea {
+                    class: "recipe",
+                    placeholder: "Save a recipe to share your mix, or paste one here",
+                    value: "{recipe_text}",
+                    oninput: move |event| { recipe_text.set(event.value()) },
+                }
+                div {
+                    class: "input-row",
+                    button {
+                        class: if flag_mix.is_none() { "disabled" } else { "" },
+                        onclick: save_recipe,
+                        "Save Recipe"
+                    }
+                    button {
+                        onclick: load_recipe,
+                        "Load Recipe"
+                    }
+                }
+                if let Some(error) = &*recipe_error.read() {
+                    span { class: "recipe-error", "{error}" }
+                }
+            }
         },
+        if !*reverse_page.read() {
         div {
             class: "input-row2",
             input {
@@ -215,16 +660,135 @@ pub fn App() -> Element {
             },
             div {
                 onclick: move |_| {
-                    let current = *option_reduce_strain.read();
-                    option_reduce_strain.set(!current);
+                    let mut filters = option_filters.write();
+                    if let Some(i) = filters.iter().position(|filter| *filter == Filter::ReduceStrain) {
+                        filters.remove(i);
+                    } else {
+                        filters.push(Filter::ReduceStrain);
+                    }
                 },
                 input {
                     type: "checkbox",
                     id: "strain-toggle",
-                    checked: *option_reduce_strain.read(),
+                    checked: option_filters.read().contains(&Filter::ReduceStrain),
                 },
                 label { for: "strain-toggle", "Reduce Eye Strain" }
             },
+            div {
+                select {
+                    id: "vision-select",
+                    onchange: move |e| {
+                        if let Some(vision) = Vision::ALL.iter().find(|vision| vision.label() == e.value()) {
+                            option_vision.set(*vision);
+                        }
+                    },
+                    for vision in Vision::ALL {
+                        option {
+                            value: vision.label(),
+                            selected: *option_vision.read() == vision,
+                            {vision.label()}
+                        }
+                    }
+                },
+                label { for: "vision-select", "Vision" }
+            },
+            div {
+                onclick: move |_| {
+                    let current = *option_explain.read();
+                    option_explain.set(!current);
+                },
+                input {
+                    type: "checkbox",
+                    id: "explain-toggle",
+                    checked: *option_explain.read(),
+                },
+                label { for: "explain-toggle", "Explain Mix" }
+            },
+            div {
+                onclick: move |_| {
+                    let resample = *option_alignment.read() == Alignment::Resample;
+                    option_alignment.set(if resample { Alignment::Lcm } else { Alignment::Resample });
+                    drop_indexed_ops();
+                },
+                input {
+                    type: "checkbox",
+                    id: "resample-toggle",
+                    checked: *option_alignment.read() == Alignment::Resample,
+                },
+                label { for: "resample-toggle", "Resample Stripes" }
+            },
+            div {
+                select {
+                    id: "mode-select",
+                    onchange: move |e| {
+                        if let Some(mode) = MixMode::ALL.iter().find(|mode| mode.label() == e.value()) {
+                            option_mode.set(*mode);
+                            drop_indexed_ops();
+                        }
+                    },
+                    for mode in MixMode::ALL {
+                        option {
+                            value: mode.label(),
+                            selected: *option_mode.read() == mode,
+                            {mode.label()}
+                        }
+                    }
+                },
+                label { for: "mode-select", "Layout" }
+            }
+            div {
+                select {
+                    id: "parity-select",
+                    disabled: *option_alignment.read() == Alignment::Resample,
+                    onchange: move |e| {
+                        if let Some(parity) = Parity::ALL.iter().find(|parity| parity.label() == e.value()) {
+                            option_parity.set(*parity);
+                            drop_indexed_ops();
+                        }
+                    },
+                    for parity in Parity::ALL {
+                        option {
+                            value: parity.label(),
+                            selected: *option_parity.read() == parity,
+                            {parity.label()}
+                        }
+                    }
+                },
+                label { for: "parity-select", "Parity" }
+            }
+            div {
+                select {
+                    id: "naming-select",
+                    onchange: move |e| {
+                        if let Some(naming) = NameTemplate::ALL.iter().find(|naming| naming.label() == e.value()) {
+                            option_naming.set(*naming);
+                        }
+                    },
+                    for naming in NameTemplate::ALL {
+                        option {
+                            value: naming.label(),
+                            selected: *option_naming.read() == naming,
+                            {naming.label()}
+                        }
+                    }
+                },
+                label { for: "naming-select", "Naming" }
+            }
+            div {
+                input {
+                    type: "range",
+                    min: 10,
+                    max: 90,
+                    step: 5,
+                    id: "ratio-toggle",
+                    value: *option_ratio.read(),
+                    onchange: move |e| {
+                        option_ratio.set(e.value().parse().expect("Can't parse the ratio value"));
+                        drop_indexed_ops();
+                    },
+                },
+                label { for: "ratio-toggle", "Balance {option_ratio}%" }
+            }
             div {
                 input {
                     type: "range",
@@ -281,10 +845,13 @@ pub fn App() -> Element {
                                             flag: flag.clone(),
                                             id: format!("{i}-{index}"),
                                             option_icons: *option_icons.read(),
-                                            option_reduce_strain: *option_reduce_strain.read(),
+                                            option_filters: option_filters.read().clone(),
                                             option_blur: *option_blur.read(),
                                             option_softness: *option_softness.read(),
-                                            other_flag: get_unselected_flag()
+                                            option_vision: *option_vision.read(),
+                                            other_flag: get_unselected_flag(),
+                                            option_alignment: *option_alignment.read(),
+                                            option_parity: *option_parity.read()
                                         },
                                         p { {flag.full_name.to_string()} }
                                     }
@@ -294,6 +861,12 @@ pub fn App() -> Element {
                 }
             }
         }
+        } else {
+            ReverseMixer {
+                render: render_options(),
+                onselect: select_decomposition,
+            }
+        }
         div {
             class: "footer",
             span { "Thanks for trying out my website! If you enjoyed it, check out the " },
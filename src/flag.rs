@@ -1,40 +1,57 @@
-use crate::flag_lib::{FlagData, Position, reduce_strain};
+use crate::flag_lib::{
+    Alignment, Color, ColorTransform, FLAG_HEIGHT, FLAG_WIDTH, Filter, FlagData, Geometry, MixMode,
+    MixTrace, Orientation, Origin, Parity, Position, Shape, Transform, Vision, describe, span,
+};
 use dioxus::prelude::*;
+use itertools::{Either, Itertools};
 
 #[derive(Props, PartialEq, Clone)]
 pub struct FlagProps {
     pub flag: FlagData,
     pub id: String,
     pub option_icons: bool,
-    pub option_reduce_strain: bool,
+    pub option_filters: Vec<Filter>,
     pub option_blur: f32,
     pub option_softness: f32,
+    pub option_vision: Vision,
     // for checking compatibility
     pub other_flag: Option<FlagData>,
+    #[props(default)]
+    pub option_alignment: Alignment,
+    #[props(default)]
+    pub option_parity: Parity,
+    /// Called with the line and column of the normalized flag that was
+    /// clicked, making stripes clickable
+    pub onpick: Option<EventHandler<(usize, usize)>>,
 }
 #[component]
 pub fn Flag(props: FlagProps) -> Element {
-    let (width, height) = (250, 150);
-    let flag = props.flag;
+    let (width, height) = (FLAG_WIDTH, FLAG_HEIGHT);
+    // Aligned flags repeat their stripes a lot, only the distinct bands are drawn
+    let flag = FlagData::normalize(&props.flag);
     let id = &props.id;
 
-    let hardness = 49. - props.option_softness;
-    let gradients: Vec<Element> = flag
-        .lines
-        .iter()
-        .enumerate()
-        .map(|(i, line)| {
-            let stops: Vec<Element> = line
-                .iter()
-                .enumerate()
-                .map(|(j, color)| {
-                    let color = if props.option_reduce_strain {
-                        reduce_strain(&color.0)
-                    } else {
-                        color.0.clone()
-                    };
-
-                    let offset = (j as f32) / ((line.len() - 1) as f32) * 100.0;
+    let paint = |color: &Color| props.option_filters.transform(*color).to_hex();
+
+    // Side by side mixes blend their columns with gradients, other layouts
+    // clip each flag to its own region
+    let gradients: Vec<Element> = if flag.mode.has_regions() {
+        vec![]
+    } else {
+        flag.lines
+            .iter()
+            .enumerate()
+            .map(|(i, line)| {
+                let colors: Vec<Color> = line
+                    .iter()
+                    .map(|color| props.option_filters.transform(*color))
+                    .collect();
+                let stops: Vec<Element> = blend_stops(
+                    &colors,
+                    &column_stops(&flag.line_weights(line), props.option_softness),
+                )
+                .into_iter()
+                .map(|(color, offset)| {
                     rsx! {
                         stop {
                             offset: "{offset}%",
@@ -43,23 +60,180 @@ pub fn Flag(props: FlagProps) -> Element {
                     }
                 })
                 .collect();
-            rsx! {
-                linearGradient {
-                    id: "grad{id}-{i}",
-                    x1: "{hardness}%",
-                    y1: "0%",
-                    x2: "{100.-hardness}%",
-                    y2: "0%",
-                    for s in stops {
-                        {s}
+                rsx! {
+                    linearGradient {
+                        id: "grad{id}-{i}",
+                        x1: "0%",
+                        y1: "0%",
+                        x2: "100%",
+                        y2: "0%",
+                        for s in stops {
+                            {s}
+                        }
                     }
                 }
-            }
-        })
+            })
+            .collect()
+    };
+
+    // Fractions of the height where every line starts, plus the bottom edge
+    let total_height = flag.total_height() as f32;
+    let offsets: Vec<f32> = flag
+        .line_offsets()
+        .iter()
+        .map(|&offset| offset as f32 / total_height)
         .collect();
+    let count = flag.source_count();
+    // Vertical bands are drawn as stripes of a transposed flag, stretched back
+    // onto the canvas. Mixed flags are transposed along with their regions.
+    let vertical = flag.orientation == Orientation::Vertical;
+    let (w, h) = (width as f32, height as f32);
+    let frame = if vertical {
+        format!("matrix(0 {} {} 0 0 0)", h / w, w / h)
+    } else {
+        String::new()
+    };
+    let gradients_len = gradients.len();
+    // Read out by screen readers and kept in exported files, hidden symbols
+    // are left out
+    let description = if props.option_icons {
+        describe(&flag, &props.option_filters)
+    } else {
+        describe(
+            &FlagData {
+                symbols: vec![],
+                ..flag.clone()
+            },
+            &props.option_filters,
+        )
+    };
+    let onpick = props.onpick;
+    let pick = move |line: usize, column: usize| {
+        if let Some(onpick) = onpick {
+            onpick.call((line, column));
+        }
+    };
+    let cursor = if onpick.is_some() { "pointer" } else { "" };
 
-    let stripe_height = height as f32 / (flag.lines.len() as f32);
-    let len = flag.lines.len();
+    let vision = props
+        .option_vision
+        .matrix()
+        .iter()
+        .map(|row| format!("{} {} {} 0 0", row[0], row[1], row[2]))
+        .chain(["0 0 0 1 0".to_string()])
+        .join(" ");
+
+    // Every mixed flag gets a clip path for its region, so that its shapes
+    // stay inside of it
+    let clips: Vec<Element> = if count == 1 {
+        vec![]
+    } else {
+        (0..count)
+            .map(|k| {
+                let points = flag
+                    .mode
+                    .region(k, &flag.weights, w, h)
+                    .iter()
+                    .map(|(x, y)| format!("{x},{y}"))
+                    .join(" ");
+                rsx! {
+                    clipPath {
+                        id: "clip{id}-{k}",
+                        polygon { points }
+                    }
+                }
+            })
+            .collect()
+    };
+
+    let layers: Vec<Element> = if !flag.mode.has_regions() {
+        vec![]
+    } else {
+        (0..count)
+            .map(|k| {
+                let (top, bottom) = flag.mode.stripe_span(k, &flag.weights, h);
+                let span = bottom - top;
+                let stripes: Vec<Element> = flag
+                    .lines
+                    .iter()
+                    .enumerate()
+                    .map(|(i, line)| {
+                        rsx! {
+                            rect {
+                                width,
+                                height: span * (offsets[i + 1] - offsets[i]),
+                                y: top + span * offsets[i],
+                                fill: paint(&line[k]),
+                                shape_rendering: "crispEdges",
+                                cursor,
+                                onclick: move |_| pick(i, k),
+                            }
+                        }
+                    })
+                    .collect();
+
+                rsx! {
+                    g {
+                        clip_path: "url(#clip{id}-{k})",
+                        for s in stripes {
+                            {s}
+                        }
+                    }
+                }
+            })
+            .collect()
+    };
+
+    // Blended lines are drawn as one gradient, so clicks are caught by a
+    // transparent rect over every column
+    let targets: Vec<Element> = if onpick.is_none() || flag.mode.has_regions() {
+        vec![]
+    } else {
+        let offsets = &offsets;
+        flag.lines
+            .iter()
+            .enumerate()
+            .flat_map(|(i, line)| {
+                let weights = flag.line_weights(line);
+                (0..line.len()).map(move |j| {
+                    let (start, end) = span(&weights, j);
+                    rsx! {
+                        rect {
+                            x: w * start,
+                            width: w * (end - start),
+                            y: h * offsets[i],
+                            height: h * (offsets[i + 1] - offsets[i]),
+                            fill: "transparent",
+                            cursor,
+                            onclick: move |_| pick(i, j),
+                        }
+                    }
+                })
+            })
+            .collect()
+    };
+
+    // Shapes of single flags are placed on the canvas, merged ones in the
+    // frame of the mix
+    let (single_shapes, merged_shapes): (Vec<Element>, Vec<Element>) =
+        flag.shapes.iter().partition_map(|(shape, position)| {
+            let element = shape_element(shape, &paint);
+            match position {
+                Position::Single => Either::Left(element),
+                Position::Merged { index, .. } => {
+                    let transform = shape_transform(&flag, shape, *index, width, height);
+                    Either::Right(rsx! {
+                        g {
+                            clip_path: "url(#clip{id}-{index})",
+                            g {
+                                transform,
+                                {element}
+                            }
+                        }
+                    })
+                }
+            }
+        });
 
     let symbols: Vec<Element> = if props.option_icons {
         flag.symbols
@@ -67,14 +241,18 @@ pub fn Flag(props: FlagProps) -> Element {
             .map(|(symbol, position)| {
                 let asset = get_asset!(symbol.src.as_str());
 
-                let t = match position {
-                    Position::Single => &symbol.single,
-                    Position::MergedLeft => &symbol.merged_left,
-                    Position::MergedRight => &symbol.get_merged_right(width),
+                let (t, mirrored) = match position {
+                    Position::Single => (symbol.single.clone(), false),
+                    Position::Merged { index, count } => {
+                        let t = symbol.get_merged(flag.mode, *index, &flag.weights, width, height);
+                        let t = if vertical { transpose_center(&t) } else { t };
+                        (t, flag.mode.is_mirrored(*index, *count))
+                    }
                 };
 
-                let transform = if *position == Position::MergedRight && symbol.mirror {
-                    format!("translate({width} 0) scale(-1 1)")
+                // Mirror around the symbol's own center so it stays in its region
+                let transform = if mirrored && symbol.mirror {
+                    format!("translate({} 0) scale(-1 1)", 2 * t.x + t.width)
                 } else {
                     String::new()
                 };
@@ -102,11 +280,22 @@ pub fn Flag(props: FlagProps) -> Element {
             height,
             view_box: format!("0 0 {width} {height}"),
             shape_rendering: "crispEdges",
-            opacity: if let Some(other) = &props.other_flag && !FlagData::is_compatible(&flag, other) { 0.4 } else { 1.0 },
+            role: "img",
+            "aria-label": "{props.flag.full_name} flag",
+            "aria-describedby": "desc{id}",
+            // Flags are mixed as they are, not normalized
+            opacity: if let Some(other) = &props.other_flag && !FlagData::is_compatible(&props.flag, other, props.option_alignment, props.option_parity) { 0.4 } else { 1.0 },
+            desc {
+                id: "desc{id}",
+                {description}
+            },
             defs {
                 for g in gradients {
                     {g}
                 },
+                for c in clips {
+                    {c}
+                },
                 filter {
                     id: "blur{id}",
                     x: "-20%",
@@ -139,19 +328,40 @@ pub fn Flag(props: FlagProps) -> Element {
                         in2: "SourceGraphic",
                         operator: "in"
                     }
+
+                    // Simulate color vision, filters work in linear RGB
+                    feColorMatrix {
+                        type: "matrix",
+                        values: vision,
+                    }
                 }
             },
 
             g {
                 filter: "url(#blur{id})",
-                for i in 0..len {
-                    rect {
-                        width,
-                        height: stripe_height,
-                        y: stripe_height * (i as f32),
-                        fill: format!("url(#grad{id}-{i})"),
-                        shape_rendering: "crispEdges"
+                g {
+                    transform: frame,
+                    for l in layers {
+                        {l}
+                    },
+                    for i in 0..gradients_len {
+                        rect {
+                            width,
+                            height: h * (offsets[i + 1] - offsets[i]),
+                            y: h * offsets[i],
+                            fill: format!("url(#grad{id}-{i})"),
+                            shape_rendering: "crispEdges"
+                        },
                     },
+                    for t in targets {
+                        {t}
+                    },
+                    for s in merged_shapes {
+                        {s}
+                    },
+                },
+                for s in single_shapes {
+                    {s}
                 },
                 for s in symbols {
                     {s}
@@ -160,3 +370,238 @@ pub fn Flag(props: FlagProps) -> Element {
         }
     }
 }
+
+#[derive(Props, PartialEq, Clone)]
+pub struct MixTraceOverlayProps {
+    /// The mix as returned along with `trace`, before any normalization
+    pub flag: FlagData,
+    pub trace: MixTrace,
+}
+
+/// Labels every line of a mix with the stripe it came from, drawn on top of
+/// the `Flag` of the mix.
+#[component]
+pub fn MixTraceOverlay(props: MixTraceOverlayProps) -> Element {
+    let (width, height) = (FLAG_WIDTH, FLAG_HEIGHT);
+    let (w, h) = (width as f32, height as f32);
+    let (flag, trace) = (&props.flag, &props.trace);
+    let total_height = flag.total_height() as f32;
+    // Lines repeated while aligning the flags come from the same stripes,
+    // they are labelled once as a single band
+    let bands: Vec<(&Vec<Origin>, f32, f32)> = trace
+        .origins
+        .iter()
+        .zip(flag.line_offsets().iter().tuple_windows())
+        .map(|(origins, (&top, &bottom))| {
+            (
+                origins,
+                top as f32 / total_height,
+                bottom as f32 / total_height,
+            )
+        })
+        .coalesce(|(origins1, top1, bottom1), (origins2, top2, bottom2)| {
+            if origins1 == origins2 {
+                Ok((origins1, top1, bottom2))
+            } else {
+                Err(((origins1, top1, bottom1), (origins2, top2, bottom2)))
+            }
+        })
+        .collect();
+
+    let labels: Vec<Element> = bands
+        .iter()
+        .flat_map(|&(origins, top, bottom)| {
+            let single = origins.len() == 1;
+            origins.iter().map(move |&origin| {
+                let k = origin.0;
+                // Lines from a single flag span the whole width
+                let x = if single {
+                    w / 2.
+                } else if flag.mode.has_regions() {
+                    let region = flag.mode.region(k, &flag.weights, w, h);
+                    region.iter().map(|(x, _)| x).sum::<f32>() / region.len() as f32
+                } else {
+                    let (start, end) = span(&flag.weights, k);
+                    w * (start + end) / 2.
+                };
+                let (start, end) = flag.mode.stripe_span(k, &flag.weights, h);
+                let y = start + (end - start) * (top + bottom) / 2.;
+                let font_size = ((end - start) * (bottom - top) * 0.8).min(12.);
+                let label = trace.label(origin);
+
+                rsx! {
+                    text {
+                        x: "{x}",
+                        y: "{y}",
+                        font_size: "{font_size}",
+                        text_anchor: "middle",
+                        dominant_baseline: "central",
+                        {label}
+                    }
+                }
+            })
+        })
+        .collect();
+
+    rsx! {
+        svg {
+            class: "mix-trace",
+            width,
+            height,
+            view_box: format!("0 0 {width} {height}"),
+            for (_, offset, _) in bands[1..].iter() {
+                line {
+                    x1: 0,
+                    x2: width,
+                    y1: "{h * offset}",
+                    y2: "{h * offset}",
+                }
+            }
+            for label in labels {
+                {label}
+            }
+        }
+    }
+}
+
+fn shape_element(shape: &Shape, paint: &dyn Fn(&Color) -> String) -> Element {
+    let fill = shape.fill.as_ref().map_or("none".to_string(), paint);
+    let stroke = shape.stroke.as_ref().map_or("none".to_string(), paint);
+    let stroke_width = shape.stroke_width;
+    let points = |points: &[(i32, i32)]| points.iter().map(|(x, y)| format!("{x},{y}")).join(" ");
+
+    match &shape.geometry {
+        Geometry::Rect {
+            x,
+            y,
+            width,
+            height,
+        } => rsx! {
+            rect { x: "{x}", y: "{y}", width: "{width}", height: "{height}", fill, stroke, stroke_width }
+        },
+        Geometry::Circle { cx, cy, r } => rsx! {
+            circle {
+                cx: "{cx}",
+                cy: "{cy}",
+                r: "{r}",
+                fill,
+                stroke,
+                stroke_width,
+                shape_rendering: "geometricPrecision"
+            }
+        },
+        Geometry::Triangle { points: p } => rsx! {
+            polygon { points: points(p), fill, stroke, stroke_width, shape_rendering: "geometricPrecision" }
+        },
+        Geometry::Polygon { points: p } => rsx! {
+            polygon { points: points(p), fill, stroke, stroke_width, shape_rendering: "geometricPrecision" }
+        },
+    }
+}
+
+/// Moves a symbol placed in a transposed mix so that it stays upright.
+fn transpose_center(t: &Transform) -> Transform {
+    let (w, h) = (FLAG_WIDTH as f32, FLAG_HEIGHT as f32);
+    let cx = (t.y as f32 + t.height as f32 / 2.) * w / h;
+    let cy = (t.x as f32 + t.width as f32 / 2.) * h / w;
+    Transform {
+        x: (cx - t.width as f32 / 2.).round() as i32,
+        y: (cy - t.height as f32 / 2.).round() as i32,
+        ..t.clone()
+    }
+}
+
+/// Moves a shape of the `index`-th mixed flag into its region. Shapes at the
+/// hoist stay attached to the outer edge of their column, others are centered
+/// in it. Shapes of squeezed flags are squeezed too.
+fn shape_transform(
+    flag: &FlagData,
+    shape: &Shape,
+    index: usize,
+    width: i32,
+    height: i32,
+) -> String {
+    let (w, h) = (width as f32, height as f32);
+    let (start, end) = span(&flag.weights, index);
+
+    match flag.mode {
+        MixMode::TopBottom => {
+            let scale = end - start;
+            format!(
+                "translate({} {}) scale({scale})",
+                w * (1. - scale) / 2.,
+                h * start
+            )
+        }
+        MixMode::Diagonal | MixMode::Chevron => String::new(),
+        MixMode::LeftRight | MixMode::Interleave | MixMode::Weave => {
+            let mirrored = flag.mode.is_mirrored(index, flag.source_count());
+            let center = w * (start + end) / 2.;
+            match (shape.geometry.touches_hoist(), mirrored) {
+                (true, false) => String::new(),
+                (true, true) => format!("translate({w} 0) scale(-1 1)"),
+                (false, false) => format!("translate({} 0)", center - w / 2.),
+                (false, true) => format!(
+                    "translate({} 0) scale(-1 1) translate({} 0)",
+                    2. * center,
+                    center - w / 2.
+                ),
+            }
+        }
+    }
+}
+
+// Stops added inside every blend, browsers would blend in sRGB otherwise
+const BLEND_STEPS: usize = 8;
+
+/// Colored gradient stops, blending the columns of `stops` perceptually.
+fn blend_stops(colors: &[Color], stops: &[(usize, f32)]) -> Vec<(Color, f32)> {
+    let blends = stops
+        .iter()
+        .tuple_windows()
+        .flat_map(|(&(j1, start), &(j2, end))| {
+            let steps = if j1 == j2 { 1 } else { BLEND_STEPS };
+            (0..steps).map(move |k| {
+                let t = k as f32 / steps as f32;
+                (
+                    colors[j1].interpolate(colors[j2], t),
+                    start + (end - start) * t,
+                )
+            })
+        });
+    let last = stops.last().map(|&(j, offset)| (colors[j], offset));
+    blends.chain(last).collect()
+}
+
+/// Gradient stops (column index, offset in percent) for a line split into
+/// columns as wide as their `weights`. Softness widens the blend around every
+/// boundary.
+fn column_stops(weights: &[u32], softness: f32) -> Vec<(usize, f32)> {
+    let len = weights.len();
+    if len == 1 {
+        return vec![(0, 0.), (0, 100.)];
+    }
+
+    let spans: Vec<(f32, f32)> = (0..len)
+        .map(|j| {
+            let (start, end) = span(weights, j);
+            (start * 100., end * 100.)
+        })
+        .collect();
+    // Blend into each neighbour proportionally to the narrower column.
+    // Inner columns have two boundaries to share their width between.
+    let blend = |j: usize| {
+        let narrower = (spans[j].1 - spans[j].0).min(spans[j + 1].1 - spans[j + 1].0);
+        let max_blend = if len > 2 { narrower / 2. } else { narrower };
+        ((1. + softness) / 50. * narrower).min(max_blend)
+    };
+
+    (0..len)
+        .flat_map(|j| {
+            let (start, end) = spans[j];
+            let start = start + if j > 0 { blend(j - 1) } else { 0. };
+            let end = end - if j + 1 < len { blend(j) } else { 0. };
+            [(j, start), (j, end)]
+        })
+        .collect()
+}
@@ -1,9 +1,36 @@
-use num_integer::lcm;
+mod color;
+mod contrast;
+mod decompose;
+mod describe;
+mod filter;
+mod naming;
+mod operations;
+mod palette;
+mod recipe;
+mod space;
+mod trace;
+mod vision;
+
+pub use color::{Color, ColorError};
+pub use contrast::ContrastReport;
+pub use decompose::{Decomposition, decompose, parse_stripes};
+pub use describe::describe;
+pub use filter::{ColorTransform, Filter};
+pub use naming::{NameTemplate, strip_variant};
+pub use operations::Operation;
+pub use palette::{Palette, PaletteFormat};
+pub use recipe::{Recipe, RenderOptions};
+pub use trace::{MixTrace, Origin, SourceTrace};
+pub use vision::Vision;
+
+use itertools::Itertools;
+use num_integer::{gcd, lcm};
 use serde::{Deserialize, Serialize};
-use std::{cmp::Ordering, collections::HashSet, iter::repeat_n};
+use std::{cmp::Ordering, collections::HashSet, fmt};
 
-#[derive(PartialEq, Eq, Clone, Debug)]
-pub struct Color(pub String);
+/// Size every flag is drawn at, shapes and symbols are placed in these coordinates
+pub const FLAG_WIDTH: i32 = 250;
+pub const FLAG_HEIGHT: i32 = 150;
 
 #[derive(PartialEq, Eq, Clone, Serialize, Deserialize, Debug)]
 pub struct Transform {
@@ -16,37 +43,529 @@ pub struct Transform {
 #[derive(PartialEq, Eq, Clone, Serialize, Deserialize, Debug)]
 pub struct SymbolData {
     pub src: String,
+    /// Color covering most of the image, checked against the stripes under it
+    pub color: Color,
     pub single: Transform,
     pub merged_left: Transform,
     pub mirror: bool,
 }
 
 impl SymbolData {
-    pub fn get_merged_right(&self, width: i32) -> Transform {
+    /// Places the symbol inside the `index`-th column, with columns as wide as
+    /// their `weights`. `merged_left` is laid out for a half-width column, so it
+    /// is scaled to fit narrower columns and mirrored inside the rightmost one.
+    pub fn get_column(&self, width: i32, height: i32, index: usize, weights: &[u32]) -> Transform {
+        let (start, end) = span(weights, index);
+        let (start, end) = (start * width as f32, end * width as f32);
+
         let transform = &self.merged_left;
+        let scale = (2. * (end - start) / width as f32).min(1.);
+        let symbol_width = transform.width as f32 * scale;
+        let symbol_height = transform.height as f32 * scale;
+
+        let x = if index + 1 == weights.len() && index > 0 {
+            end - (transform.x as f32 * scale) - symbol_width
+        } else {
+            start + transform.x as f32 * scale
+        };
+        let y = transform.y as f32 * scale + height as f32 * (1. - scale) / 2.;
+
         Transform {
-            x: width - transform.x - transform.width,
-            y: transform.y,
-            width: transform.width,
-            height: transform.height,
+            x: x.round() as i32,
+            y: y.round() as i32,
+            width: symbol_width.round() as i32,
+            height: symbol_height.round() as i32,
+        }
+    }
+
+    /// Places the symbol of the `index`-th mixed flag, the flags sharing the
+    /// canvas according to their `weights`.
+    pub fn get_merged(
+        &self,
+        mode: MixMode,
+        index: usize,
+        weights: &[u32],
+        width: i32,
+        height: i32,
+    ) -> Transform {
+        let (w, h) = (width as f32, height as f32);
+        let (start, end) = span(weights, index);
+        match mode {
+            // Interleaved flags are both full width, so symbols keep to their sides
+            MixMode::LeftRight | MixMode::Interleave | MixMode::Weave => {
+                self.get_column(width, height, index, weights)
+            }
+            MixMode::TopBottom => {
+                // The whole flag is squeezed into its band, so is the symbol
+                let scale = end - start;
+                let t = &self.single;
+                Transform {
+                    x: (t.x as f32 * scale + w * (1. - scale) / 2.).round() as i32,
+                    y: (h * start + t.y as f32 * scale).round() as i32,
+                    width: (t.width as f32 * scale).round() as i32,
+                    height: (t.height as f32 * scale).round() as i32,
+                }
+            }
+            MixMode::Diagonal => {
+                // Center of the band on the main diagonal
+                let along = (start + end) / 2.;
+                self.centered(w * along, h * along, 2. * (end - start))
+            }
+            MixMode::Chevron => {
+                if index == 0 {
+                    return self.single.clone();
+                }
+                let inner = if index + 1 < weights.len() {
+                    chevron_back(weights, index + 1, w)
+                } else {
+                    0.
+                };
+                let outer = chevron_back(weights, index, w);
+                self.centered((inner + outer) / 2. + h / 8., h / 2., end - start)
+            }
+        }
+    }
+
+    /// `merged_left` scaled by `scale` and centered on the given point.
+    fn centered(&self, cx: f32, cy: f32, scale: f32) -> Transform {
+        let width = self.merged_left.width as f32 * scale;
+        let height = self.merged_left.height as f32 * scale;
+        Transform {
+            x: (cx - width / 2.).round() as i32,
+            y: (cy - height / 2.).round() as i32,
+            width: width.round() as i32,
+            height: height.round() as i32,
         }
     }
 }
 
-#[derive(PartialEq, Eq, Clone, Debug)]
+/// Outline of a shape, in the coordinates of a `FLAG_WIDTH` x `FLAG_HEIGHT` flag.
+#[derive(PartialEq, Eq, Clone, Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Geometry {
+    Rect {
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+    },
+    Triangle {
+        points: [(i32, i32); 3],
+    },
+    Circle {
+        cx: i32,
+        cy: i32,
+        r: i32,
+    },
+    Polygon {
+        points: Vec<(i32, i32)>,
+    },
+}
+
+impl Geometry {
+    /// Whether the shape starts at the hoist (left edge) of the flag, like the
+    /// Czech triangle or the Progress chevron.
+    pub fn touches_hoist(&self) -> bool {
+        match self {
+            Geometry::Rect { x, .. } => *x <= 0,
+            Geometry::Triangle { points } => points.iter().any(|(x, _)| *x <= 0),
+            Geometry::Circle { cx, r, .. } => cx - r <= 0,
+            Geometry::Polygon { points } => points.iter().any(|(x, _)| *x <= 0),
+        }
+    }
+
+    /// Top left and bottom right corners of the box around the shape.
+    pub fn bounds(&self) -> ((i32, i32), (i32, i32)) {
+        let corners = match self {
+            Geometry::Rect {
+                x,
+                y,
+                width,
+                height,
+            } => vec![(*x, *y), (x + width, y + height)],
+            Geometry::Triangle { points } => points.to_vec(),
+            Geometry::Circle { cx, cy, r } => vec![(cx - r, cy - r), (cx + r, cy + r)],
+            Geometry::Polygon { points } => points.clone(),
+        };
+        let (xs, ys): (Vec<i32>, Vec<i32>) = corners.into_iter().unzip();
+        let min = |v: &[i32]| v.iter().copied().min().unwrap_or(0);
+        let max = |v: &[i32]| v.iter().copied().max().unwrap_or(0);
+        ((min(&xs), min(&ys)), (max(&xs), max(&ys)))
+    }
+}
+
+/// Shape as written in flags.json, colors referencing the flag's palette.
+#[derive(PartialEq, Eq, Clone, Serialize, Deserialize, Debug)]
+pub struct ShapeJSON {
+    #[serde(flatten)]
+    pub geometry: Geometry,
+    pub fill: Option<usize>,
+    pub stroke: Option<usize>,
+    #[serde(default)]
+    pub stroke_width: i32,
+}
+
+#[derive(PartialEq, Eq, Clone, Serialize, Deserialize, Debug)]
+pub struct Shape {
+    pub geometry: Geometry,
+    pub fill: Option<Color>,
+    pub stroke: Option<Color>,
+    pub stroke_width: i32,
+}
+
+#[derive(PartialEq, Eq, Clone, Serialize, Deserialize, Debug)]
 pub enum Position {
     Single,
-    MergedLeft,
-    MergedRight,
+    /// Symbol of the `index`-th out of `count` mixed flags
+    Merged {
+        index: usize,
+        count: usize,
+    },
 }
 
-#[derive(PartialEq, Eq, Clone, Debug)]
+/// How the flags of a mix share the canvas.
+#[derive(PartialEq, Eq, Clone, Copy, Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum MixMode {
+    #[default]
+    LeftRight,
+    TopBottom,
+    Diagonal,
+    /// The first flag is the background, the others are nested chevrons on the
+    /// left, like on the Progress Pride flag
+    Chevron,
+    /// Full-width stripes of every flag alternate: A1, B1, A2, B2, ...
+    Interleave,
+    /// Like `Interleave`, but on aligned stripes so each flag keeps its proportions
+    Weave,
+}
+
+/// How stripe counts of the mixed flags are brought together.
+#[derive(PartialEq, Eq, Clone, Copy, Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Alignment {
+    /// Even out stripe counts according to the `Parity` strategy, then repeat
+    /// every stripe up to the least common multiple of the stripe counts
+    #[default]
+    Lcm,
+    /// Sample every flag onto the same number of equal bands
+    Resample,
+}
+
+/// How odd flags are mixed with even ones when aligning on the least common
+/// multiple. Flags are odd or even by their total height rather than their
+/// stripe count, so that unequal stripes that already line up are left alone.
+#[derive(PartialEq, Eq, Clone, Copy, Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Parity {
+    /// The middle stripe of odd flags grows by one unit, which doubles it when
+    /// all stripes are equal
+    #[default]
+    DuplicateMiddle,
+    /// The edge stripes of odd flags grow by half a unit each, keeping the
+    /// middle intact
+    DuplicateEdges,
+    /// Even flags get a neutral stripe one unit high in their middle
+    Separator,
+    /// Every stripe of odd flags is split in two, keeping exact proportions
+    /// at the cost of more bands
+    Proportional,
+}
+
+// Color of the stripe inserted by `Parity::Separator`
+const SEPARATOR_COLOR: Color = Color::WHITE;
+
+impl Parity {
+    pub const ALL: [Parity; 4] = [
+        Parity::DuplicateMiddle,
+        Parity::DuplicateEdges,
+        Parity::Separator,
+        Parity::Proportional,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Parity::DuplicateMiddle => "Duplicate Middle",
+            Parity::DuplicateEdges => "Duplicate Edges",
+            Parity::Separator => "Separator",
+            Parity::Proportional => "Proportional",
+        }
+    }
+
+    /// Whether a flag with a total height of `total` has to be evened out
+    /// among flags of the given parities.
+    fn applies(&self, total: u32, has_even: bool, has_odd: bool) -> bool {
+        match self {
+            Parity::Separator => has_odd && total.is_multiple_of(2),
+            _ => has_even && !total.is_multiple_of(2),
+        }
+    }
+
+    /// Evens out the flag, also returning the original stripe of every line.
+    /// Stripes added from nowhere have none.
+    pub fn apply(&self, flag: &FlagData) -> (FlagData, Vec<Option<usize>>) {
+        let len = flag.lines.len();
+        match self {
+            Parity::DuplicateMiddle => {
+                let mut heights = flag.heights.clone();
+                heights[flag.line_at(flag.total_height() / 2)] += 1;
+                let flag = FlagData {
+                    heights,
+                    ..flag.clone()
+                };
+                (flag, (0..len).map(Some).collect())
+            }
+            Parity::DuplicateEdges => {
+                // In half units, the edges only take one more each
+                let mut heights: Vec<u32> = flag.heights.iter().map(|height| height * 2).collect();
+                heights[0] += 1;
+                heights[len - 1] += 1;
+                let flag = FlagData {
+                    heights,
+                    ..flag.clone()
+                };
+                (flag, (0..len).map(Some).collect())
+            }
+            Parity::Separator => {
+                // At the stripe boundary closest to the middle of the height
+                let center = flag.total_height() / 2;
+                let offsets = flag.line_offsets();
+                let mid = (1..len)
+                    .min_by_key(|&i| offsets[i].abs_diff(center))
+                    .unwrap_or(len);
+                let mut flag = flag.clone();
+                let width = flag.lines[0].len();
+                flag.lines.insert(mid, vec![SEPARATOR_COLOR; width]);
+                flag.heights.insert(mid, 1);
+                let origins = (0..=len)
+                    .map(|i| match i.cmp(&mid) {
+                        Ordering::Less => Some(i),
+                        Ordering::Equal => None,
+                        Ordering::Greater => Some(i - 1),
+                    })
+                    .collect();
+                (flag, origins)
+            }
+            Parity::Proportional => {
+                let origins = (0..2 * len).map(|i| Some(i / 2)).collect();
+                (FlagData::multiply(flag, 2), origins)
+            }
+        }
+    }
+
+    /// Evens out every flag that needs it among `flags`, the others are
+    /// left as they are.
+    fn apply_all(&self, flags: &[&FlagData]) -> Vec<Option<(FlagData, Vec<Option<usize>>)>> {
+        let has_even = flags
+            .iter()
+            .any(|flag| flag.total_height().is_multiple_of(2));
+        let has_odd = flags
+            .iter()
+            .any(|flag| !flag.total_height().is_multiple_of(2));
+        flags
+            .iter()
+            .map(|flag| {
+                self.applies(flag.total_height(), has_even, has_odd)
+                    .then(|| self.apply(flag))
+            })
+            .collect()
+    }
+}
+
+// Bands per stripe of the longest flag when resampling
+const RESAMPLE_DETAIL: usize = 2;
+
+#[derive(PartialEq, Eq, Clone, Serialize, Deserialize, Debug)]
+#[serde(default)]
+pub struct MixOptions {
+    pub mode: MixMode,
+    pub alignment: Alignment,
+    pub parity: Parity,
+    pub naming: NameTemplate,
+    /// Share of the canvas taken by the first flag in percent, the rest is
+    /// split evenly among the other flags
+    pub ratio: u32,
+}
+
+impl Default for MixOptions {
+    fn default() -> Self {
+        MixOptions {
+            mode: MixMode::default(),
+            alignment: Alignment::default(),
+            parity: Parity::default(),
+            naming: NameTemplate::default(),
+            ratio: 50,
+        }
+    }
+}
+
+impl MixOptions {
+    pub fn weights(&self, count: usize) -> Vec<u32> {
+        let ratio = self.ratio.clamp(1, 99);
+        // Scaled by the number of other flags to stay whole
+        let others = count.saturating_sub(1).max(1) as u32;
+        (0..count)
+            .map(|i| if i == 0 { ratio * others } else { 100 - ratio })
+            .collect()
+    }
+}
+
+pub type Point = (f32, f32);
+
+// Chevron depth as a fraction of the flag width, taken from the Progress Pride flag
+const CHEVRON_DEPTH: f32 = 0.26;
+
+impl MixMode {
+    pub const ALL: [MixMode; 6] = [
+        MixMode::LeftRight,
+        MixMode::TopBottom,
+        MixMode::Diagonal,
+        MixMode::Chevron,
+        MixMode::Interleave,
+        MixMode::Weave,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            MixMode::LeftRight => "Left / Right",
+            MixMode::TopBottom => "Top / Bottom",
+            MixMode::Diagonal => "Diagonal",
+            MixMode::Chevron => "Chevron",
+            MixMode::Interleave => "Interleave",
+            MixMode::Weave => "Weave",
+        }
+    }
+
+    /// Whether every flag is drawn clipped to its own region rather than as
+    /// gradient columns over shared stripes.
+    pub fn has_regions(&self) -> bool {
+        matches!(
+            self,
+            MixMode::TopBottom | MixMode::Diagonal | MixMode::Chevron
+        )
+    }
+
+    /// Area covered by the `index`-th flag, the flags sharing the canvas
+    /// according to their `weights`. Later areas are drawn on top of earlier ones.
+    pub fn region(&self, index: usize, weights: &[u32], width: f32, height: f32) -> Vec<Point> {
+        let rect = vec![(0., 0.), (width, 0.), (width, height), (0., height)];
+        let (start, end) = span(weights, index);
+
+        match self {
+            MixMode::LeftRight | MixMode::Interleave | MixMode::Weave => vec![
+                (width * start, 0.),
+                (width * end, 0.),
+                (width * end, height),
+                (width * start, height),
+            ],
+            MixMode::TopBottom => vec![
+                (0., height * start),
+                (width, height * start),
+                (width, height * end),
+                (0., height * end),
+            ],
+            MixMode::Diagonal => {
+                // Bands along the anti-diagonals x / width + y / height = const
+                let diagonal = |(x, y): Point| x / width + y / height;
+                let region = clip_polygon(&rect, |p| diagonal(p) - 2. * start);
+                clip_polygon(&region, |p| 2. * end - diagonal(p))
+            }
+            MixMode::Chevron => {
+                if index == 0 {
+                    return rect;
+                }
+                let back = chevron_back(weights, index, width);
+                let tip = back + height / 2.;
+                vec![
+                    (0., 0.),
+                    (back, 0.),
+                    (tip, height / 2.),
+                    (back, height),
+                    (0., height),
+                ]
+            }
+        }
+    }
+
+    /// Vertical span the stripes of the `index`-th flag are squeezed into.
+    pub fn stripe_span(&self, index: usize, weights: &[u32], height: f32) -> (f32, f32) {
+        match self {
+            MixMode::TopBottom => {
+                let (start, end) = span(weights, index);
+                (height * start, height * end)
+            }
+            _ => (0., height),
+        }
+    }
+
+    /// Whether symbols of the `index`-th flag should be mirrored when possible.
+    pub fn is_mirrored(&self, index: usize, count: usize) -> bool {
+        !matches!(self, MixMode::TopBottom | MixMode::Chevron) && count > 1 && index + 1 == count
+    }
+}
+
+/// Fraction of the canvas taken by the `index`-th of the weighted parts.
+pub fn span(weights: &[u32], index: usize) -> (f32, f32) {
+    let total = weights.iter().sum::<u32>().max(1) as f32;
+    let before = weights[..index].iter().sum::<u32>() as f32;
+    (before / total, (before + weights[index] as f32) / total)
+}
+
+/// Distance from the left edge to the back of the `index`-th chevron, which
+/// grows with the weights of it and every chevron nested inside it.
+fn chevron_back(weights: &[u32], index: usize, width: f32) -> f32 {
+    let (start, _) = span(weights, index);
+    width * CHEVRON_DEPTH * 2. * (1. - start)
+}
+
+/// Keeps the part of a convex polygon where `side` is non-negative.
+fn clip_polygon(polygon: &[Point], side: impl Fn(Point) -> f32) -> Vec<Point> {
+    let mut clipped = vec![];
+    for (i, &p) in polygon.iter().enumerate() {
+        let q = polygon[(i + 1) % polygon.len()];
+        let (dp, dq) = (side(p), side(q));
+        if dp >= 0. {
+            clipped.push(p);
+        }
+        if (dp >= 0.) != (dq >= 0.) {
+            let t = dp / (dp - dq);
+            clipped.push((p.0 + t * (q.0 - p.0), p.1 + t * (q.1 - p.1)));
+        }
+    }
+    clipped
+}
+
+/// Direction the lines of a flag run in.
+#[derive(PartialEq, Eq, Clone, Copy, Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Orientation {
+    /// Stripes from top to bottom
+    #[default]
+    Horizontal,
+    /// Bands from the hoist to the fly (left to right)
+    Vertical,
+}
+
+#[derive(PartialEq, Eq, Clone, Serialize, Deserialize, Debug)]
 pub struct FlagData {
+    /// Unique across flags, unlike the names shared by variants
+    pub id: String,
     pub full_name: String,
     pub name: String,
+    /// Form of the name used before another name, like "Bi"
+    pub adjective: String,
+    /// Form of the name used at the start of a portmanteau, like "Trans"
+    pub prefix: String,
     pub categories: HashSet<String>,
     pub lines: Vec<Vec<Color>>,
+    /// Relative height of every line
+    pub heights: Vec<u32>,
+    pub orientation: Orientation,
     pub symbols: Vec<(SymbolData, Position)>,
+    pub shapes: Vec<(Shape, Position)>,
+    pub mode: MixMode,
+    /// Relative share of the canvas of every mixed flag
+    pub weights: Vec<u32>,
 }
 
 impl PartialOrd for FlagData {
@@ -63,20 +582,76 @@ impl Ord for FlagData {
 
 #[derive(PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct FlagDataJSON {
+    pub id: String,
     pub full_name: String,
     pub name: String,
+    /// Same as `name` if omitted
+    pub adjective: Option<String>,
+    /// Same as `adjective` if omitted
+    pub prefix: Option<String>,
     pub lines: Vec<String>,
+    /// Relative stripe heights, equal if omitted
+    pub heights: Option<Vec<u32>>,
+    /// Horizontal if omitted
+    pub orientation: Option<Orientation>,
     pub categories: Vec<String>,
     pub symbol: Option<SymbolData>,
+    /// Colors referenced by `shapes`
+    pub palette: Option<Vec<String>>,
+    pub shapes: Option<Vec<ShapeJSON>>,
+}
+
+/// Why an entry of flags.json can't be turned into a flag.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum FlagError {
+    NoLines,
+    Color(ColorError),
+    /// Number of heights given for the number of lines
+    Heights(usize, usize),
+    /// Index of a line with a height of zero
+    ZeroHeight(usize),
+    /// Index of a shape color missing from the palette
+    Palette(usize),
+}
+
+impl fmt::Display for FlagError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FlagError::NoLines => write!(f, "the flag has no lines"),
+            FlagError::Color(e) => write!(f, "{e}"),
+            FlagError::Heights(heights, lines) => {
+                write!(f, "{heights} heights given for {lines} lines")
+            }
+            FlagError::ZeroHeight(i) => write!(f, "line {} has a height of zero", i + 1),
+            FlagError::Palette(i) => write!(f, "no color {i} in the palette"),
+        }
+    }
+}
+
+impl From<ColorError> for FlagError {
+    fn from(e: ColorError) -> Self {
+        FlagError::Color(e)
+    }
 }
 
 impl FlagData {
-    pub fn from_json(json: &FlagDataJSON) -> FlagData {
+    pub fn from_json(json: &FlagDataJSON) -> Result<FlagData, FlagError> {
+        if json.lines.is_empty() {
+            return Err(FlagError::NoLines);
+        }
         let lines = json
             .lines
             .iter()
-            .map(|line| vec![Color(line.clone())])
-            .collect();
+            .map(|line| Ok(vec![line.parse()?]))
+            .collect::<Result<_, ColorError>>()?;
+
+        let heights = json.heights.clone().unwrap_or(vec![1; json.lines.len()]);
+        if heights.len() != json.lines.len() {
+            return Err(FlagError::Heights(heights.len(), json.lines.len()));
+        }
+        if let Some(i) = heights.iter().position(|&height| height == 0) {
+            return Err(FlagError::ZeroHeight(i));
+        }
 
         let categories = json.categories.iter().cloned().collect();
 
@@ -86,189 +661,446 @@ impl FlagData {
             vec![]
         };
 
-        FlagData {
+        let palette: Vec<Color> = json
+            .palette
+            .iter()
+            .flatten()
+            .map(|color| color.parse())
+            .collect::<Result<_, _>>()?;
+        let from_palette = |index: Option<usize>| {
+            index
+                .map(|i| palette.get(i).copied().ok_or(FlagError::Palette(i)))
+                .transpose()
+        };
+        let shapes = json
+            .shapes
+            .iter()
+            .flatten()
+            .map(|shape| {
+                let shape = Shape {
+                    geometry: shape.geometry.clone(),
+                    fill: from_palette(shape.fill)?,
+                    stroke: from_palette(shape.stroke)?,
+                    stroke_width: shape.stroke_width,
+                };
+                Ok((shape, Position::Single))
+            })
+            .collect::<Result<_, FlagError>>()?;
+
+        let adjective = json.adjective.clone().unwrap_or(json.name.clone());
+        let prefix = json.prefix.clone().unwrap_or(adjective.clone());
+
+        Ok(FlagData {
+            id: json.id.clone(),
             full_name: json.full_name.clone(),
             name: json.name.clone(),
+            adjective,
+            prefix,
             lines,
+            heights,
+            orientation: json.orientation.unwrap_or_default(),
             categories,
             symbols,
+            shapes,
+            mode: MixMode::default(),
+            weights: vec![1],
+        })
+    }
+
+    pub fn total_height(&self) -> u32 {
+        self.heights.iter().sum()
+    }
+
+    /// Distance from the top to the start of every line, plus the total height.
+    pub fn line_offsets(&self) -> Vec<u32> {
+        let mut offset = 0;
+        let mut offsets = vec![0];
+        for height in &self.heights {
+            offset += height;
+            offsets.push(offset);
+        }
+        offsets
+    }
+
+    /// Index of the line covering the given height.
+    fn line_at(&self, offset: u32) -> usize {
+        self.line_offsets()
+            .iter()
+            .skip(1)
+            .position(|&end| offset < end)
+            .unwrap_or(self.lines.len() - 1)
+    }
+
+    /// Column weights for one of the lines, equal unless the line holds a
+    /// color for every mixed flag.
+    pub fn line_weights(&self, line: &[Color]) -> Vec<u32> {
+        if line.len() == self.weights.len() {
+            self.weights.clone()
+        } else {
+            vec![1; line.len()]
         }
     }
 
-    pub fn duplicate_middle(flag: &FlagData) -> FlagData {
-        let mid = flag.lines.len() / 2;
+    /// Number of flags sharing the canvas.
+    pub fn source_count(&self) -> usize {
+        self.weights.len()
+    }
 
+    pub fn multiply(flag: &FlagData, factor: usize) -> FlagData {
         let lines = flag
             .lines
             .iter()
-            .enumerate()
-            .flat_map(|(i, line)| repeat_n(line.clone(), 1 + (i == mid) as usize))
+            .flat_map(|line| std::iter::repeat_n(line.clone(), factor))
             .collect();
+        let heights = flag
+            .heights
+            .iter()
+            .flat_map(|height| std::iter::repeat_n(*height, factor))
+            .collect();
+        FlagData {
+            lines,
+            heights,
+            ..flag.clone()
+        }
+    }
 
+    /// Stretches the flag onto `bands` equal stripes, each taking the color of
+    /// the original stripe under its center.
+    pub fn resample(flag: &FlagData, bands: usize) -> FlagData {
+        // Work in units of 1 / (2 * bands) of the total height to stay exact
+        let total = flag.total_height() as usize;
+        let lines = (0..bands)
+            .map(|i| {
+                let center = (2 * i + 1) * total / (2 * bands);
+                flag.lines[flag.line_at(center as u32)].clone()
+            })
+            .collect();
         FlagData {
             lines,
+            heights: vec![1; bands],
             ..flag.clone()
         }
     }
 
-    pub fn multiply(flag: &FlagData, factor: usize) -> FlagData {
-        let lines = flag
-            .lines
+    /// Cuts flags of the same total height at every stripe boundary of any of
+    /// them, so they all end up with the same stripe heights.
+    pub fn merge_bands(flags: &[FlagData]) -> Vec<FlagData> {
+        let boundaries: Vec<u32> = flags
             .iter()
-            .flat_map(|line| std::iter::repeat_n(line.clone(), factor))
+            .flat_map(|flag| flag.line_offsets())
+            .sorted()
+            .dedup()
             .collect();
+        let heights: Vec<u32> = boundaries.windows(2).map(|w| w[1] - w[0]).collect();
+
+        flags
+            .iter()
+            .map(|flag| FlagData {
+                lines: boundaries[..boundaries.len() - 1]
+                    .iter()
+                    .map(|&start| flag.lines[flag.line_at(start)].clone())
+                    .collect(),
+                heights: heights.clone(),
+                ..flag.clone()
+            })
+            .collect()
+    }
+
+    /// Merges identical adjacent lines into one band as high as all of them,
+    /// then reduces the heights to their smallest whole ratio. The flag looks
+    /// the same but takes far fewer elements to draw.
+    pub fn normalize(flag: &FlagData) -> FlagData {
+        let (lines, heights): (Vec<Vec<Color>>, Vec<u32>) = flag
+            .lines
+            .iter()
+            .cloned()
+            .zip(flag.heights.iter().copied())
+            .coalesce(|(line1, height1), (line2, height2)| {
+                if line1 == line2 {
+                    Ok((line1, height1 + height2))
+                } else {
+                    Err(((line1, height1), (line2, height2)))
+                }
+            })
+            .unzip();
+        let divisor = heights
+            .iter()
+            .fold(0, |acc, &height| gcd(acc, height))
+            .max(1);
+
         FlagData {
             lines,
+            heights: heights.iter().map(|height| height / divisor).collect(),
             ..flag.clone()
         }
     }
 
-    pub fn mix(flag1: &FlagData, flag2: &FlagData) -> FlagData {
-        let mut flag1 = flag1.clone();
-        let mut flag2 = flag2.clone();
-
-        if flag1.name == flag2.name {
-            return flag1;
+    /// Turns vertical bands into stripes, the hoist band on top.
+    pub fn to_horizontal(flag: &FlagData) -> FlagData {
+        FlagData {
+            orientation: Orientation::Horizontal,
+            ..flag.clone()
         }
+    }
 
-        let mut len1 = flag1.lines.len();
-        let mut len2 = flag2.lines.len();
+    /// Mixes any number of flags according to `options`.
+    /// Flags repeating an earlier one are skipped. Vertical flags
+    /// are turned horizontal first, so the result is always horizontal.
+    ///
+    /// Panics if `flags` is empty.
+    pub fn mix_many(flags: &[FlagData], options: &MixOptions) -> FlagData {
+        FlagData::mix_traced(flags, options).0
+    }
 
-        match (len1 % 2, len2 % 2) {
-            (1, 0) => {
-                flag1 = FlagData::duplicate_middle(&flag1);
-                len1 += 1;
-            }
-            (0, 1) => {
-                flag2 = FlagData::duplicate_middle(&flag2);
-                len2 += 1;
-            }
-            _ => {}
+    /// Same as `mix_many`, also telling where every line of the mix came from.
+    pub fn mix_traced(flags: &[FlagData], options: &MixOptions) -> (FlagData, MixTrace) {
+        let mode = options.mode;
+        assert!(!flags.is_empty(), "Nothing to mix");
+
+        let flags: Vec<&FlagData> = flags.iter().unique_by(|flag| &flag.id).collect();
+        let source_trace = |flag: &FlagData| SourceTrace {
+            name: flag.name.clone(),
+            stripes: flag.lines.len(),
+            parity: None,
+            factor: 1,
+        };
+        if flags.len() == 1 {
+            let trace = MixTrace {
+                alignment: None,
+                sources: vec![source_trace(flags[0])],
+                bands: flags[0].lines.len(),
+                origins: (0..flags[0].lines.len())
+                    .map(|i| vec![(0, Some(i))])
+                    .collect(),
+            };
+            return (flags[0].clone(), trace);
         }
+        let horizontal: Vec<FlagData> = flags.iter().map(|f| FlagData::to_horizontal(f)).collect();
+        let flags: Vec<&FlagData> = horizontal.iter().collect();
+        let mut sources: Vec<SourceTrace> = flags.iter().map(|flag| source_trace(flag)).collect();
 
-        let lcm = lcm(len1, len2);
-        let flag1_multiplied = FlagData::multiply(&flag1, lcm / len1);
-        let flag2_multiplied = FlagData::multiply(&flag2, lcm / len2);
+        // Original stripe of every flag in every aligned band
+        let (bands, aligned, band_origins): (usize, Vec<FlagData>, Vec<Vec<Option<usize>>>) =
+            match options.alignment {
+                Alignment::Lcm => {
+                    // Stripe counts have to share parity as soon as both odd and
+                    // even flags are around
+                    let (even, even_origins): (Vec<FlagData>, Vec<Vec<Option<usize>>>) = options
+                        .parity
+                        .apply_all(&flags)
+                        .into_iter()
+                        .zip(&flags)
+                        .zip(sources.iter_mut())
+                        .map(|((evened, flag), source)| match evened {
+                            Some(evened) => {
+                                source.parity = Some(options.parity);
+                                evened
+                            }
+                            None => ((*flag).clone(), (0..flag.lines.len()).map(Some).collect()),
+                        })
+                        .unzip();
 
-        let full_name = if flag1.full_name != flag2.full_name {
-            format!("{} {}", flag1.full_name, flag2.full_name)
-        } else {
-            flag1.full_name.clone()
-        };
+                    let lcm = even
+                        .iter()
+                        .fold(1, |acc, flag| lcm(acc, flag.total_height()));
+                    let multiplied: Vec<FlagData> = even
+                        .iter()
+                        .zip(sources.iter_mut())
+                        .map(|(flag, source)| {
+                            source.factor = (lcm / flag.total_height()) as usize;
+                            FlagData::multiply(flag, source.factor)
+                        })
+                        .collect();
+                    let merged = FlagData::merge_bands(&multiplied);
 
-        let name = format!("{} {}", flag1.name, flag2.name);
+                    let starts = merged[0].line_offsets();
+                    let origins = starts[..starts.len() - 1]
+                        .iter()
+                        .map(|&start| {
+                            multiplied
+                                .iter()
+                                .zip(&sources)
+                                .zip(&even_origins)
+                                .map(|((flag, source), origins)| {
+                                    // Undo the multiplication, then the evening out
+                                    origins[flag.line_at(start) / source.factor]
+                                })
+                                .collect()
+                        })
+                        .collect();
+                    (merged[0].lines.len(), merged, origins)
+                }
+                Alignment::Resample => {
+                    let longest = flags.iter().map(|flag| flag.lines.len()).max().unwrap_or(1);
+                    let bands = longest * RESAMPLE_DETAIL;
+                    let resampled = flags
+                        .iter()
+                        .map(|flag| FlagData::resample(flag, bands))
+                        .collect();
+                    let origins = (0..bands)
+                        .map(|i| {
+                            flags
+                                .iter()
+                                .map(|flag| {
+                                    let total = flag.total_height() as usize;
+                                    Some(flag.line_at(((2 * i + 1) * total / (2 * bands)) as u32))
+                                })
+                                .collect()
+                        })
+                        .collect();
+                    (bands, resampled, origins)
+                }
+            };
 
-        let lines = flag1_multiplied
-            .lines
+        // Variants are only told apart when mixed with each other
+        let full_name = flags
             .iter()
-            .zip(flag2_multiplied.lines.iter())
-            .map(|(line1, line2)| {
-                line1
+            .map(|flag| {
+                let stripped = strip_variant(&flag.full_name);
+                let variants = flags
                     .iter()
-                    .chain(line2.iter())
-                    .cloned()
-                    .collect::<Vec<Color>>()
+                    .filter(|other| strip_variant(&other.full_name) == stripped)
+                    .count();
+                if variants > 1 {
+                    flag.full_name.as_str()
+                } else {
+                    stripped
+                }
             })
-            .collect();
+            .join(" + ");
 
-        let categories = flag1.categories.union(&flag2.categories).cloned().collect();
+        let name = options.naming.apply(&flags);
 
-        let symbols = flag1
-            .symbols
+        let (lines, heights, origins): (Vec<Vec<Color>>, Vec<u32>, Vec<Vec<Origin>>) = match mode {
+            // Original stripes take turns, longer flags finish on their own.
+            // Heights are scaled so every flag keeps the same total height.
+            MixMode::Interleave => {
+                let total = flags
+                    .iter()
+                    .fold(1, |acc, flag| lcm(acc, flag.total_height()));
+                let longest = flags.iter().map(|flag| flag.lines.len()).max().unwrap_or(0);
+                (0..longest)
+                    .flat_map(|i| {
+                        flags.iter().enumerate().filter_map(move |(k, flag)| {
+                            let scale = total / flag.total_height();
+                            Some((
+                                flag.lines.get(i)?.clone(),
+                                flag.heights[i] * scale,
+                                vec![(k, Some(i))],
+                            ))
+                        })
+                    })
+                    .multiunzip()
+            }
+            // Aligned stripes take turns, keeping every flag's proportions
+            MixMode::Weave => (0..bands)
+                .flat_map(|i| {
+                    let band_origins = &band_origins;
+                    aligned.iter().enumerate().map(move |(k, flag)| {
+                        (
+                            flag.lines[i].clone(),
+                            flag.heights[i],
+                            vec![(k, band_origins[i][k])],
+                        )
+                    })
+                })
+                .multiunzip(),
+            _ => (
+                (0..bands)
+                    .map(|i| {
+                        aligned
+                            .iter()
+                            .flat_map(|flag| flag.lines[i].iter())
+                            .cloned()
+                            .collect::<Vec<Color>>()
+                    })
+                    .collect(),
+                aligned[0].heights.clone(),
+                band_origins
+                    .iter()
+                    .map(|band| band.iter().copied().enumerate().collect())
+                    .collect(),
+            ),
+        };
+
+        let categories = flags
             .iter()
-            .map(|(s, _)| (s.clone(), Position::MergedLeft))
-            .chain(
-                flag2
-                    .symbols
+            .flat_map(|flag| flag.categories.iter())
+            .cloned()
+            .collect();
+
+        let count = flags.len();
+        let symbols = flags
+            .iter()
+            .enumerate()
+            .flat_map(|(index, flag)| {
+                flag.symbols
                     .iter()
-                    .map(|(s, _)| (s.clone(), Position::MergedRight)),
-            )
+                    .map(move |(s, _)| (s.clone(), Position::Merged { index, count }))
+            })
+            .collect();
+        let shapes = flags
+            .iter()
+            .enumerate()
+            .flat_map(|(index, flag)| {
+                flag.shapes
+                    .iter()
+                    .map(move |(s, _)| (s.clone(), Position::Merged { index, count }))
+            })
             .collect();
 
-        FlagData {
+        // Interleaved flags keep their original stripes
+        let (alignment, sources) = if mode == MixMode::Interleave {
+            (None, flags.iter().map(|flag| source_trace(flag)).collect())
+        } else {
+            (Some(options.alignment), sources)
+        };
+        let trace = MixTrace {
+            alignment,
+            sources,
+            bands,
+            origins,
+        };
+
+        let flag = FlagData {
+            id: flags.iter().map(|flag| flag.id.as_str()).join("+"),
             full_name,
+            adjective: name.clone(),
+            prefix: name.clone(),
             name,
             lines,
+            heights,
+            orientation: Orientation::Horizontal,
             categories,
             symbols,
-        }
+            shapes,
+            mode,
+            weights: options.weights(count),
+        };
+        (flag, trace)
     }
 
-    pub fn is_compatible(flag1: &FlagData, flag2: &FlagData) -> bool {
-        let mut len1 = flag1.lines.len();
-        let mut len2 = flag2.lines.len();
-
-        match (len1 % 2, len2 % 2) {
-            (1, 0) => {
-                len1 += 1;
-            }
-            (0, 1) => {
-                len2 += 1;
-            }
-            _ => {}
+    /// Whether the two flags mix without blowing up the stripe count.
+    pub fn is_compatible(
+        flag1: &FlagData,
+        flag2: &FlagData,
+        alignment: Alignment,
+        parity: Parity,
+    ) -> bool {
+        if alignment == Alignment::Resample {
+            return true;
         }
 
+        let (len1, len2) = parity
+            .apply_all(&[flag1, flag2])
+            .into_iter()
+            .zip([flag1, flag2])
+            .map(|(evened, flag)| {
+                evened.map_or(flag.total_height(), |(evened, _)| evened.total_height())
+            })
+            .collect_tuple()
+            .expect("Two flags in, two flags out");
+
         len2.is_multiple_of(len1) || len1.is_multiple_of(len2)
     }
 }
-
-pub fn reduce_strain(hex: &str) -> String {
-    let hex = hex.trim_start_matches('#');
-    let r = u8::from_str_radix(&hex[0..2], 16).unwrap() as f32 / 255.0;
-    let g = u8::from_str_radix(&hex[2..4], 16).unwrap() as f32 / 255.0;
-    let b = u8::from_str_radix(&hex[4..6], 16).unwrap() as f32 / 255.0;
-
-    // Detect near-grayscale
-    if (r - g).abs() < 0.02 && (r - b).abs() < 0.02 && (g - b).abs() < 0.02 {
-        let mut l = (r + g + b) / 3.0;
-        l = l.clamp(0.2, 0.85);
-        let val = (l * 255.0).round() as u8;
-        return format!("#{val:02X}{val:02X}{val:02X}");
-    }
-
-    // Convert to HSL
-    let max = r.max(g).max(b);
-    let min = r.min(g).min(b);
-    let l = (max + min) / 2.0;
-
-    let delta = max - min;
-    let s = if delta == 0.0 {
-        0.0
-    } else {
-        delta / (1.0 - (2.0 * l - 1.0).abs())
-    };
-
-    let h = if delta == 0.0 {
-        0.0
-    } else if max == r {
-        ((g - b) / delta).rem_euclid(6.0)
-    } else if max == g {
-        ((b - r) / delta) + 2.0
-    } else {
-        ((r - g) / delta) + 4.0
-    } * 60.0;
-
-    // Softening adjustments
-    let l = l.clamp(0.3, 0.8);
-    let s = s.clamp(0.5, 0.8);
-
-    // Convert back to RGB
-    let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
-    let x = c * (1.0 - ((h / 60.0) % 2.0 - 1.0).abs());
-    let m = l - c / 2.0;
-
-    let (r1, g1, b1) = match h {
-        h if h < 60.0 => (c, x, 0.0),
-        h if h < 120.0 => (x, c, 0.0),
-        h if h < 180.0 => (0.0, c, x),
-        h if h < 240.0 => (0.0, x, c),
-        h if h < 300.0 => (x, 0.0, c),
-        _ => (c, 0.0, x),
-    };
-
-    let r = ((r1 + m) * 255.0).round() as u8;
-    let g = ((g1 + m) * 255.0).round() as u8;
-    let b = ((b1 + m) * 255.0).round() as u8;
-
-    format!("#{r:02X}{g:02X}{b:02X}")
-}
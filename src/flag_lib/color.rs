@@ -0,0 +1,232 @@
+use super::space::{Lab, LinearRgb, Oklab, Oklch};
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+mod css;
+mod names;
+
+/// An sRGB color with alpha, written as "#RRGGBB" or "#RRGGBBAA". Parsing
+/// also accepts the short hex forms, `rgb()`, `hsl()` and CSS color names.
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize, Debug)]
+#[serde(try_from = "String", into = "String")]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum ColorError {
+    /// Neither hex nor a known function
+    Syntax(String),
+    /// Not 3, 4, 6 or 8 hex digits
+    Length(String),
+    InvalidDigit(String),
+    /// Arguments of `rgb()` or `hsl()` that are missing or not numbers
+    Arguments(String),
+    UnknownName(String),
+}
+
+impl fmt::Display for ColorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ColorError::Syntax(s) => {
+                write!(f, "\"{s}\" is not a color like #5BCEFA or rgb(91 206 250)")
+            }
+            ColorError::Length(s) => write!(f, "\"{s}\" needs 3, 4, 6 or 8 hex digits"),
+            ColorError::InvalidDigit(s) => write!(f, "\"{s}\" has a digit that isn't hex"),
+            ColorError::Arguments(s) => write!(f, "\"{s}\" has invalid arguments"),
+            ColorError::UnknownName(s) => write!(f, "\"{s}\" is not a CSS color name"),
+        }
+    }
+}
+
+impl Color {
+    pub const WHITE: Color = Color::rgb(255, 255, 255);
+
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Color {
+        Color { r, g, b, a: 255 }
+    }
+
+    /// "#RGB", "#RGBA", "#RRGGBB" or "#RRGGBBAA".
+    pub fn from_hex(hex: &str) -> Result<Color, ColorError> {
+        let digits = hex
+            .strip_prefix('#')
+            .ok_or_else(|| ColorError::Syntax(hex.to_string()))?;
+        if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(ColorError::InvalidDigit(hex.to_string()));
+        }
+        // Short forms repeat every digit
+        let digits = match digits.len() {
+            3 | 4 => digits.chars().flat_map(|c| [c, c]).collect(),
+            6 | 8 => digits.to_string(),
+            _ => return Err(ColorError::Length(hex.to_string())),
+        };
+        let channel = |i: usize| u8::from_str_radix(&digits[2 * i..2 * i + 2], 16).unwrap_or(0);
+        Ok(Color {
+            a: if digits.len() == 8 { channel(3) } else { 255 },
+            ..Color::rgb(channel(0), channel(1), channel(2))
+        })
+    }
+
+    /// "#RRGGBB", with the alpha appended when not opaque.
+    pub fn to_hex(self) -> String {
+        let Color { r, g, b, a } = self;
+        if a == 255 {
+            format!("#{r:02X}{g:02X}{b:02X}")
+        } else {
+            format!("#{r:02X}{g:02X}{b:02X}{a:02X}")
+        }
+    }
+
+    /// Channels between 0 and 1.
+    pub fn to_rgb(self) -> (f32, f32, f32) {
+        (
+            self.r as f32 / 255.,
+            self.g as f32 / 255.,
+            self.b as f32 / 255.,
+        )
+    }
+
+    /// Channels between 0 and 1 are rounded to the nearest byte, alpha is kept.
+    pub fn with_rgb(&self, (r, g, b): (f32, f32, f32)) -> Color {
+        let byte = |v: f32| (v.clamp(0., 1.) * 255.).round() as u8;
+        Color {
+            a: self.a,
+            ..Color::rgb(byte(r), byte(g), byte(b))
+        }
+    }
+
+    /// Hue in degrees, saturation and lightness between 0 and 1.
+    pub fn to_hsl(self) -> (f32, f32, f32) {
+        let (r, g, b) = self.to_rgb();
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let l = (max + min) / 2.0;
+
+        let delta = max - min;
+        let s = if delta == 0.0 {
+            0.0
+        } else {
+            delta / (1.0 - (2.0 * l - 1.0).abs())
+        };
+
+        let h = if delta == 0.0 {
+            0.0
+        } else if max == r {
+            ((g - b) / delta).rem_euclid(6.0)
+        } else if max == g {
+            ((b - r) / delta) + 2.0
+        } else {
+            ((r - g) / delta) + 4.0
+        } * 60.0;
+
+        (h, s, l)
+    }
+
+    /// The color with the given hue, saturation and lightness, alpha is kept.
+    pub fn with_hsl(&self, (h, s, l): (f32, f32, f32)) -> Color {
+        let h = h.rem_euclid(360.);
+        let (s, l) = (s.clamp(0., 1.), l.clamp(0., 1.));
+        let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
+        let x = c * (1.0 - ((h / 60.0) % 2.0 - 1.0).abs());
+        let m = l - c / 2.0;
+
+        let (r1, g1, b1) = match h {
+            h if h < 60.0 => (c, x, 0.0),
+            h if h < 120.0 => (x, c, 0.0),
+            h if h < 180.0 => (0.0, c, x),
+            h if h < 240.0 => (0.0, x, c),
+            h if h < 300.0 => (x, 0.0, c),
+            _ => (c, 0.0, x),
+        };
+
+        self.with_rgb((r1 + m, g1 + m, b1 + m))
+    }
+
+    pub fn to_linear(self) -> LinearRgb {
+        LinearRgb::from_srgb(self.to_rgb())
+    }
+
+    /// Channels out of gamut are clipped, alpha is kept.
+    pub fn with_linear(self, linear: LinearRgb) -> Color {
+        self.with_rgb(linear.to_srgb())
+    }
+
+    pub fn to_oklab(self) -> Oklab {
+        Oklab::from(self.to_linear())
+    }
+
+    pub fn to_oklch(self) -> Oklch {
+        Oklch::from(self.to_oklab())
+    }
+
+    /// Colors out of gamut lose chroma until they fit, alpha is kept.
+    pub fn with_oklch(self, lch: Oklch) -> Color {
+        self.with_linear(lch.to_gamut())
+    }
+
+    pub fn to_lab(self) -> Lab {
+        Lab::from(self.to_linear())
+    }
+
+    /// Perceptual distance in OKLab, about 0.02 for colors that are hard to
+    /// tell apart.
+    pub fn distance(&self, other: &Color) -> f32 {
+        self.to_oklab().distance(&other.to_oklab())
+    }
+
+    /// CIE76 difference in CIELAB, the usual ΔE.
+    pub fn delta_e(&self, other: &Color) -> f32 {
+        self.to_lab().delta_e(&other.to_lab())
+    }
+
+    /// Closest of a few dozen everyday names, like "mint" or "dark green".
+    pub fn name(self) -> &'static str {
+        names::nearest(self)
+    }
+
+    /// The color `t` of the way to `other`, blended in OKLCH.
+    pub fn interpolate(self, other: Color, t: f32) -> Color {
+        let alpha = self.a as f32 + (other.a as f32 - self.a as f32) * t;
+        Color {
+            a: alpha.round() as u8,
+            ..self
+        }
+        .with_oklch(self.to_oklch().interpolate(other.to_oklch(), t))
+    }
+}
+
+impl FromStr for Color {
+    type Err = ColorError;
+
+    fn from_str(s: &str) -> Result<Color, ColorError> {
+        let s = s.trim();
+        if s.starts_with('#') {
+            Color::from_hex(s)
+        } else {
+            css::parse(s)
+        }
+    }
+}
+
+impl TryFrom<String> for Color {
+    type Error = ColorError;
+
+    fn try_from(s: String) -> Result<Color, ColorError> {
+        s.parse()
+    }
+}
+
+impl From<Color> for String {
+    fn from(color: Color) -> String {
+        color.to_hex()
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_hex())
+    }
+}
@@ -0,0 +1,64 @@
+use super::Color;
+
+/// The closest everyday name, as a shade a person would call it rather than
+/// the exact CSS name.
+pub fn nearest(color: Color) -> &'static str {
+    if color.a == 0 {
+        return "transparent";
+    }
+    let color = Color { a: 255, ..color };
+    NAMES
+        .iter()
+        .min_by(|(_, a), (_, b)| {
+            let [a, b] = [a, b].map(|&(r, g, b)| color.distance(&Color::rgb(r, g, b)));
+            a.total_cmp(&b)
+        })
+        .map_or("", |(name, _)| name)
+}
+
+// Picked to cover the stripes of pride flags, mostly the shades their own
+// descriptions use
+const NAMES: [(&str, (u8, u8, u8)); 42] = [
+    ("black", (0, 0, 0)),
+    ("dark gray", (64, 64, 64)),
+    ("gray", (128, 128, 128)),
+    ("light gray", (192, 192, 192)),
+    ("white", (255, 255, 255)),
+    ("dark red", (139, 0, 0)),
+    ("red", (228, 3, 3)),
+    ("dark orange", (213, 45, 0)),
+    ("orange", (255, 140, 0)),
+    ("light orange", (255, 154, 86)),
+    ("peach", (255, 204, 170)),
+    ("brown", (120, 79, 23)),
+    ("tan", (210, 180, 140)),
+    ("beige", (245, 240, 210)),
+    ("gold", (230, 180, 20)),
+    ("yellow", (255, 237, 0)),
+    ("pale yellow", (255, 248, 160)),
+    ("olive", (128, 128, 0)),
+    ("lime", (140, 230, 40)),
+    ("green", (0, 128, 38)),
+    ("dark green", (0, 90, 40)),
+    ("light green", (166, 227, 161)),
+    ("mint", (152, 232, 193)),
+    ("teal", (7, 141, 112)),
+    ("turquoise", (38, 206, 170)),
+    ("cyan", (0, 230, 240)),
+    ("light blue", (91, 206, 250)),
+    ("sky blue", (135, 206, 235)),
+    ("blue", (0, 77, 255)),
+    ("royal blue", (0, 56, 168)),
+    ("dark blue", (0, 0, 128)),
+    ("indigo", (75, 0, 130)),
+    ("purple", (117, 7, 135)),
+    ("violet", (155, 79, 150)),
+    ("lavender", (181, 126, 220)),
+    ("lilac", (200, 162, 200)),
+    ("magenta", (214, 2, 112)),
+    ("hot pink", (255, 105, 180)),
+    ("pink", (245, 169, 184)),
+    ("light pink", (255, 209, 220)),
+    ("dark pink", (163, 2, 98)),
+    ("coral", (255, 127, 80)),
+];
@@ -0,0 +1,329 @@
+use super::{
+    Color, ColorTransform, FLAG_HEIGHT, FLAG_WIDTH, FlagData, Geometry, MixMode, Orientation,
+    Position, RenderOptions, span,
+};
+use itertools::Itertools;
+
+/// Distance under which touching colors are hard to tell apart at a glance,
+/// black and white being 1 apart
+const LOW_CONTRAST: f32 = 0.1;
+
+/// Where two colors touch on a normalized flag.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Edge {
+    /// Between a line and the next one, in the `column`-th mixed flag
+    Stripes { column: usize, line: usize },
+    /// Between the `column`-th mixed flag and the next one, on a line
+    Columns { column: usize, line: usize },
+    /// Between the region of the `column`-th mixed flag and the next one,
+    /// where `line` of the first meets `next_line` of the second
+    Regions {
+        column: usize,
+        line: usize,
+        next_line: usize,
+    },
+    /// Between the `shape`-th shape and a line under it
+    Shape { shape: usize, line: usize },
+    /// Between the `shape`-th shape and an earlier one it is drawn on
+    Overlap { shape: usize, below: usize },
+    /// Between the main color of the `symbol`-th symbol and a line under it
+    Symbol { symbol: usize, line: usize },
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct Contrast {
+    pub edge: Edge,
+    /// As drawn, with filters and simulated vision. Symbols are images, which
+    /// only go through the simulated vision.
+    pub colors: [Color; 2],
+    /// Perceptual difference, see `Color::distance`
+    pub delta: f32,
+}
+
+impl Contrast {
+    pub fn is_low(&self) -> bool {
+        self.delta < LOW_CONTRAST
+    }
+
+    /// Where the colors touch and how different they are, as a CIELAB ΔE.
+    /// Columns are lettered like in `MixTrace`.
+    pub fn describe(&self, columns: usize) -> String {
+        let letter = |column: usize| (b'A' + column as u8) as char;
+        let place = match self.edge {
+            Edge::Stripes { column, line } if columns > 1 => {
+                format!(
+                    "Stripes {} and {} of {}",
+                    line + 1,
+                    line + 2,
+                    letter(column)
+                )
+            }
+            Edge::Stripes { line, .. } => format!("Stripes {} and {}", line + 1, line + 2),
+            Edge::Columns { column, line } => format!(
+                "{} and {} on stripe {}",
+                letter(column),
+                letter(column + 1),
+                line + 1
+            ),
+            Edge::Regions {
+                column,
+                line,
+                next_line,
+            } if line == next_line => format!(
+                "{} and {} on stripe {}",
+                letter(column),
+                letter(column + 1),
+                line + 1
+            ),
+            Edge::Regions {
+                column,
+                line,
+                next_line,
+            } => format!(
+                "Stripe {} of {} and stripe {} of {}",
+                line + 1,
+                letter(column),
+                next_line + 1,
+                letter(column + 1)
+            ),
+            Edge::Shape { shape, line } => format!("Shape {} on stripe {}", shape + 1, line + 1),
+            Edge::Overlap { shape, below } => {
+                format!("Shape {} on shape {}", shape + 1, below + 1)
+            }
+            Edge::Symbol { symbol, line } => {
+                format!("Symbol {} on stripe {}", symbol + 1, line + 1)
+            }
+        };
+        let delta_e = self.colors[0].delta_e(&self.colors[1]);
+        format!("{place}: ΔE {delta_e:.1}")
+    }
+}
+
+/// How well every pair of touching colors of a flag can be told apart, as
+/// drawn with the given render options.
+#[derive(PartialEq, Clone, Debug)]
+pub struct ContrastReport {
+    /// Colors per line of the normalized flag
+    pub columns: usize,
+    /// Every pair of colors once, lowest contrast first
+    pub contrasts: Vec<Contrast>,
+}
+
+impl ContrastReport {
+    pub fn new(flag: &FlagData, render: &RenderOptions) -> ContrastReport {
+        let flag = FlagData::normalize(flag);
+        let lines = &flag.lines;
+        let columns = lines.first().map_or(0, Vec::len);
+
+        let stripes =
+            lines
+                .iter()
+                .tuple_windows()
+                .enumerate()
+                .flat_map(|(line, (above, below))| {
+                    above
+                        .iter()
+                        .zip(below)
+                        .enumerate()
+                        .map(move |(column, (&c1, &c2))| (Edge::Stripes { column, line }, c1, c2))
+                });
+        // Columns only meet side by side when they are blended, other
+        // layouts have regions with their own boundaries
+        let sides =
+            if flag.mode.has_regions() {
+                region_edges(&flag)
+            } else {
+                lines
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(line, colors)| {
+                        colors.iter().tuple_windows().enumerate().map(
+                            move |(column, (&c1, &c2))| (Edge::Columns { column, line }, c1, c2),
+                        )
+                    })
+                    .collect()
+            };
+        // Columns a shape or symbol of the given position is drawn on
+        let behind = |position: &Position| match position {
+            Position::Merged { index, .. } if *index < columns => *index..index + 1,
+            _ => 0..columns,
+        };
+        let shapes = flag
+            .shapes
+            .iter()
+            .enumerate()
+            .flat_map(|(shape, (data, position))| {
+                // The stroke is what meets the background, when there is one
+                let outline = data.stroke.filter(|_| data.stroke_width > 0).or(data.fill);
+                // Shapes drawn on top of another one, like nested chevrons,
+                // only touch that one
+                let below = flag.shapes[..shape]
+                    .iter()
+                    .rposition(|(other, other_position)| {
+                        other_position == position && contains(&other.geometry, &data.geometry)
+                    });
+                if let Some(below) = below {
+                    let background = flag.shapes[below].0.fill;
+                    return vec![(Edge::Overlap { shape, below }, outline, background)];
+                }
+                under(&flag, &data.geometry)
+                    .into_iter()
+                    .cartesian_product(behind(position))
+                    .map(|(line, column)| {
+                        (
+                            Edge::Shape { shape, line },
+                            outline,
+                            Some(lines[line][column]),
+                        )
+                    })
+                    .collect()
+            })
+            .filter_map(|(edge, c1, c2)| Some((edge, c1?, c2?)));
+
+        let symbols = flag
+            .symbols
+            .iter()
+            .enumerate()
+            .filter(|_| render.icons)
+            .flat_map(|(symbol, (data, position))| {
+                let lines_under = match position {
+                    Position::Single => {
+                        let t = &data.single;
+                        let rect = Geometry::Rect {
+                            x: t.x,
+                            y: t.y,
+                            width: t.width,
+                            height: t.height,
+                        };
+                        under(&flag, &rect)
+                    }
+                    // Placed in the frame of the mix, where the stripes of
+                    // squeezed flags only take their own band
+                    Position::Merged { index, .. } => {
+                        let (w, h) = (FLAG_WIDTH, FLAG_HEIGHT);
+                        let t = data.get_merged(flag.mode, *index, &flag.weights, w, h);
+                        let (top, bottom) = flag.mode.stripe_span(*index, &flag.weights, h as f32);
+                        let fraction = |y: i32| (y as f32 - top) / (bottom - top);
+                        lines_between(&flag, fraction(t.y), fraction(t.y + t.height))
+                    }
+                };
+                lines_under
+                    .into_iter()
+                    .cartesian_product(behind(position))
+                    .map(move |(line, column)| {
+                        (
+                            Edge::Symbol { symbol, line },
+                            data.color,
+                            lines[line][column],
+                        )
+                    })
+            });
+
+        let paint = |color: Color| render.vision.transform(render.filters.transform(color));
+        let contrasts = stripes
+            .chain(sides)
+            .chain(shapes)
+            .chain(symbols)
+            // Colors that only become the same once painted are what we are
+            // after, symbols have no outline to stand out by otherwise
+            .filter(|(edge, c1, c2)| c1 != c2 || matches!(edge, Edge::Symbol { .. }))
+            .unique_by(|(_, c1, c2)| {
+                let mut pair = [c1.to_hex(), c2.to_hex()];
+                pair.sort();
+                pair
+            })
+            .map(|(edge, c1, c2)| {
+                let colors = match edge {
+                    Edge::Symbol { .. } => [render.vision.transform(c1), paint(c2)],
+                    _ => [paint(c1), paint(c2)],
+                };
+                Contrast {
+                    edge,
+                    colors,
+                    delta: colors[0].distance(&colors[1]),
+                }
+            })
+            .sorted_by(|a, b| a.delta.total_cmp(&b.delta))
+            .collect();
+
+        ContrastReport { columns, contrasts }
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &Contrast> {
+        self.contrasts.iter().filter(|contrast| contrast.is_low())
+    }
+}
+
+/// Lines of neighbouring mixed flags that meet where their regions do.
+/// Regions are in the frame of the mix, so this holds for vertical mixes too.
+fn region_edges(flag: &FlagData) -> Vec<(Edge, Color, Color)> {
+    let lines = &flag.lines;
+
+    (0..flag.source_count().saturating_sub(1))
+        .flat_map(|column| {
+            let pairs: Vec<(usize, usize)> = match flag.mode {
+                // Every flag is squeezed into its band, so the bottom line
+                // of one meets the top line of the next
+                MixMode::TopBottom => vec![(lines.len() - 1, 0)],
+                // The boundary x / width + y / height = 2 * end only spans
+                // part of the height
+                MixMode::Diagonal => {
+                    let (_, end) = span(&flag.weights, column);
+                    lines_between(flag, 2. * end - 1., 2. * end)
+                        .into_iter()
+                        .map(|line| (line, line))
+                        .collect()
+                }
+                // Every chevron reaches from the top to the bottom of the one
+                // it is nested in
+                _ => (0..lines.len()).map(|line| (line, line)).collect(),
+            };
+            pairs.into_iter().map(move |(line, next_line)| {
+                (
+                    Edge::Regions {
+                        column,
+                        line,
+                        next_line,
+                    },
+                    lines[line][column],
+                    lines[next_line][column + 1],
+                )
+            })
+        })
+        .collect()
+}
+
+/// Whether the box around `inner` is inside of the one around `outer`.
+fn contains(outer: &Geometry, inner: &Geometry) -> bool {
+    let ((left, top), (right, bottom)) = outer.bounds();
+    let ((x1, y1), (x2, y2)) = inner.bounds();
+    left <= x1 && top <= y1 && x2 <= right && y2 <= bottom
+}
+
+/// Lines of the flag that the shape covers at least partly.
+fn under(flag: &FlagData, geometry: &Geometry) -> Vec<usize> {
+    let ((left, top), (right, bottom)) = geometry.bounds();
+    // Along the stripes, as fractions of the flag
+    let (start, end) = match flag.orientation {
+        Orientation::Horizontal => (
+            top as f32 / FLAG_HEIGHT as f32,
+            bottom as f32 / FLAG_HEIGHT as f32,
+        ),
+        Orientation::Vertical => (
+            left as f32 / FLAG_WIDTH as f32,
+            right as f32 / FLAG_WIDTH as f32,
+        ),
+    };
+    lines_between(flag, start, end)
+}
+
+/// Lines of the flag crossing the part of its height from `start` to `end`,
+/// as fractions.
+fn lines_between(flag: &FlagData, start: f32, end: f32) -> Vec<usize> {
+    let total = flag.total_height() as f32;
+    flag.line_offsets()
+        .iter()
+        .tuple_windows()
+        .positions(|(&above, &below)| above as f32 / total < end && below as f32 / total > start)
+        .collect()
+}
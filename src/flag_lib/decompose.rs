@@ -0,0 +1,161 @@
+use super::{Alignment, Color, ColorError, FlagData, MixMode, MixOptions, Parity, SEPARATOR_COLOR};
+use itertools::Itertools;
+use std::{collections::HashSet, fmt};
+
+/// A pair of flags that mixes into the searched flag.
+#[derive(PartialEq, Clone, Debug)]
+pub struct Decomposition {
+    pub sources: [FlagData; 2],
+    pub options: MixOptions,
+}
+
+// Colors picked from an image are rarely exact
+const TOLERANCE: f32 = 0.02;
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum StripesError {
+    Empty,
+    Color(ColorError),
+    /// Line number of the first line with a different number of colors
+    UnevenColumns(usize),
+}
+
+impl fmt::Display for StripesError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StripesError::Empty => write!(f, "No stripes to search for"),
+            StripesError::Color(e) => write!(f, "{e}"),
+            StripesError::UnevenColumns(line) => {
+                write!(f, "Line {line} has a different number of colors")
+            }
+        }
+    }
+}
+
+/// Reads a flag from one line of colors per stripe, with a color per mixed
+/// flag on every line, like "#5BCEFA #E40303". Repeated lines make taller
+/// stripes.
+pub fn parse_stripes(text: &str) -> Result<FlagData, StripesError> {
+    let lines: Vec<Vec<Color>> = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            line.split(|c: char| c.is_whitespace() || c == ',' || c == '/')
+                .filter(|token| !token.is_empty())
+                .map(|token| token.parse().map_err(StripesError::Color))
+                .collect()
+        })
+        .collect::<Result<_, _>>()?;
+
+    let width = lines.first().ok_or(StripesError::Empty)?.len();
+    if let Some(uneven) = lines.iter().position(|line| line.len() != width) {
+        return Err(StripesError::UnevenColumns(uneven + 1));
+    }
+
+    Ok(FlagData {
+        id: String::new(),
+        full_name: String::new(),
+        name: String::new(),
+        adjective: String::new(),
+        prefix: String::new(),
+        categories: HashSet::new(),
+        heights: vec![1; lines.len()],
+        lines,
+        orientation: Default::default(),
+        symbols: vec![],
+        shapes: vec![],
+        mode: MixMode::default(),
+        weights: vec![1; width],
+    })
+}
+
+/// Searches `catalog` for ordered pairs of flags whose mix draws the same
+/// stripes as `target`, whatever the alignment and parity it takes. Every
+/// pair is listed once, with the first options that reproduce it.
+pub fn decompose(target: &FlagData, catalog: &[FlagData]) -> Vec<Decomposition> {
+    let wanted = FlagData::normalize(target);
+    let width = wanted.lines.first().map_or(0, Vec::len);
+    // Side by side layouts only differ in how they are drawn, interleaved
+    // ones in their stripes
+    let modes = match width {
+        1 => vec![MixMode::Interleave, MixMode::Weave],
+        2 => vec![target.mode],
+        _ => return vec![],
+    };
+    let alignments: Vec<(Alignment, Parity)> = Parity::ALL
+        .iter()
+        .map(|&parity| (Alignment::Lcm, parity))
+        .chain([(Alignment::Resample, Parity::default())])
+        .collect();
+
+    // Colors the sources have to provide, separators come for free
+    let columns: Vec<Vec<Color>> = (0..width)
+        .map(|k| {
+            wanted
+                .lines
+                .iter()
+                .map(|line| line[k])
+                .filter(|color| color.distance(&SEPARATOR_COLOR) >= TOLERANCE)
+                .unique()
+                .collect()
+        })
+        .collect();
+    let palettes: Vec<Vec<Color>> = catalog.iter().map(palette).collect();
+    let has = |i: usize, color: &Color| {
+        palettes[i]
+            .iter()
+            .any(|other| other.distance(color) < TOLERANCE)
+    };
+    let provides = |i: usize, j: usize| match columns.as_slice() {
+        [both] => both.iter().all(|color| has(i, color) || has(j, color)),
+        [first, second] => {
+            first.iter().all(|color| has(i, color)) && second.iter().all(|color| has(j, color))
+        }
+        _ => false,
+    };
+
+    (0..catalog.len())
+        .cartesian_product(0..catalog.len())
+        .filter(|&(i, j)| i != j && catalog[i].id != catalog[j].id && provides(i, j))
+        .flat_map(|(i, j)| {
+            let sources = [catalog[i].clone(), catalog[j].clone()];
+            let candidates = modes
+                .iter()
+                .cartesian_product(&alignments)
+                .map(|(&mode, &(alignment, parity))| MixOptions {
+                    mode,
+                    alignment,
+                    parity,
+                    ..MixOptions::default()
+                })
+                .collect_vec();
+            // One match per layout is enough
+            candidates
+                .into_iter()
+                .filter(|options| same_stripes(&FlagData::mix_many(&sources, options), &wanted))
+                .dedup_by(|a, b| a.mode == b.mode)
+                .map(|options| Decomposition {
+                    sources: sources.clone(),
+                    options,
+                })
+                .collect_vec()
+        })
+        .collect()
+}
+
+/// Whether the mix draws `wanted`, which is already normalized.
+fn same_stripes(mix: &FlagData, wanted: &FlagData) -> bool {
+    let mix = FlagData::normalize(mix);
+    mix.heights == wanted.heights
+        && mix.lines.iter().zip(&wanted.lines).all(|(line1, line2)| {
+            line1.len() == line2.len()
+                && line1
+                    .iter()
+                    .zip(line2)
+                    .all(|(c1, c2)| c1.distance(c2) < TOLERANCE)
+        })
+}
+
+fn palette(flag: &FlagData) -> Vec<Color> {
+    flag.lines.iter().flatten().copied().unique().collect()
+}
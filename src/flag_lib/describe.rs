@@ -0,0 +1,139 @@
+use super::{
+    Color, ColorTransform, FLAG_HEIGHT, FLAG_WIDTH, Filter, FlagData, Geometry, MixMode,
+    Orientation, Point, Position, Shape, span,
+};
+use itertools::Itertools;
+
+/// Describes a flag in words for screen readers, like "7 horizontal stripes:
+/// dark green, teal, mint, white, light blue, royal blue and indigo; white
+/// triangle on the left". Colors are named as drawn with `filters`.
+pub fn describe(flag: &FlagData, filters: &[Filter]) -> String {
+    let flag = FlagData::normalize(flag);
+    let name = |color: Color| filters.transform(color).name();
+
+    let lines = flag
+        .lines
+        .iter()
+        .map(|line| line.iter().map(|&color| name(color)).dedup().join(" / "))
+        .collect_vec();
+    let kind = match flag.orientation {
+        Orientation::Horizontal => "horizontal stripes",
+        Orientation::Vertical => "vertical bands",
+    };
+    let mut description = match lines.as_slice() {
+        [line] => format!("{line} background"),
+        _ => format!("{} {kind}: {}", lines.len(), sentence(&lines)),
+    };
+
+    let count = flag.source_count();
+    if count > 1 {
+        description = format!("{count} flags {}, {description}", arrangement(flag.mode));
+    }
+
+    let shapes = flag.shapes.iter().filter_map(|(shape, position)| {
+        let color = shape.fill.or(shape.stroke)?;
+        let place = match position {
+            Position::Single => center(&shape.geometry),
+            Position::Merged { index, .. } => region_center(&flag, *index),
+        };
+        Some(format!(
+            "{} {} {}",
+            name(color),
+            shape_kind(shape),
+            place_words(place)
+        ))
+    });
+    let symbols = flag.symbols.iter().map(|(symbol, position)| {
+        let place = match position {
+            Position::Single => {
+                let t = &symbol.single;
+                (
+                    t.x as f32 + t.width as f32 / 2.,
+                    t.y as f32 + t.height as f32 / 2.,
+                )
+            }
+            Position::Merged { index, .. } => region_center(&flag, *index),
+        };
+        // Symbols are only known by their image, like "heart.svg"
+        let file = symbol.src.rsplit('/').next().unwrap_or_default();
+        let stem = file.split('.').next().unwrap_or_default();
+        format!("{stem} symbol {}", place_words(place))
+    });
+
+    for detail in shapes.chain(symbols).dedup() {
+        description.push_str("; ");
+        description.push_str(&detail);
+    }
+    description
+}
+
+/// "a", "a and b", "a, b and c".
+fn sentence(items: &[String]) -> String {
+    match items {
+        [] => String::new(),
+        [item] => item.clone(),
+        [rest @ .., last] => format!("{} and {last}", rest.join(", ")),
+    }
+}
+
+fn arrangement(mode: MixMode) -> &'static str {
+    match mode {
+        MixMode::LeftRight => "side by side",
+        MixMode::TopBottom => "one above the other",
+        MixMode::Diagonal => "split diagonally",
+        MixMode::Chevron => "as nested chevrons",
+        MixMode::Interleave => "interleaved",
+        MixMode::Weave => "woven together",
+    }
+}
+
+fn shape_kind(shape: &Shape) -> &'static str {
+    match &shape.geometry {
+        Geometry::Rect { .. } => "rectangle",
+        Geometry::Triangle { .. } => "triangle",
+        Geometry::Circle { .. } if shape.fill.is_none() => "ring",
+        Geometry::Circle { .. } => "circle",
+        Geometry::Polygon { .. } if shape.geometry.touches_hoist() => "chevron",
+        Geometry::Polygon { .. } => "shape",
+    }
+}
+
+fn center(geometry: &Geometry) -> Point {
+    let ((left, top), (right, bottom)) = geometry.bounds();
+    ((left + right) as f32 / 2., (top + bottom) as f32 / 2.)
+}
+
+/// Middle of the area of the `index`-th mixed flag, on the canvas.
+fn region_center(flag: &FlagData, index: usize) -> Point {
+    let (w, h) = (FLAG_WIDTH as f32, FLAG_HEIGHT as f32);
+    let (x, y) = if flag.mode.has_regions() {
+        let region = flag.mode.region(index, &flag.weights, w, h);
+        let len = region.len().max(1) as f32;
+        (
+            region.iter().map(|(x, _)| x).sum::<f32>() / len,
+            region.iter().map(|(_, y)| y).sum::<f32>() / len,
+        )
+    } else {
+        let (start, end) = span(&flag.weights, index);
+        (w * (start + end) / 2., h / 2.)
+    };
+    // Vertical mixes are drawn transposed
+    match flag.orientation {
+        Orientation::Horizontal => (x, y),
+        Orientation::Vertical => (y * w / h, x * h / w),
+    }
+}
+
+/// Where a point is on the flag, by thirds: "at the top right", "on the
+/// left" or "in the center".
+fn place_words((x, y): Point) -> String {
+    let third = |v: f32, size: i32| (3. * v / size as f32).floor().clamp(0., 2.) as usize;
+    let vertical = ["top", "", "bottom"][third(y, FLAG_HEIGHT)];
+    let horizontal = ["left", "", "right"][third(x, FLAG_WIDTH)];
+    match (vertical, horizontal) {
+        ("", "") => "in the center".to_string(),
+        (side, "") => format!("at the {side}"),
+        ("", side) => format!("on the {side}"),
+        (vertical, horizontal) => format!("at the {vertical} {horizontal}"),
+    }
+}
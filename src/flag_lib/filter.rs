@@ -0,0 +1,153 @@
+use super::{Color, space::Oklch};
+use serde::{Deserialize, Serialize};
+
+/// Changes every color of a flag before it is drawn.
+pub trait ColorTransform {
+    fn transform(&self, color: Color) -> Color;
+}
+
+/// Transforms are chained in order.
+impl<T: ColorTransform> ColorTransform for [T] {
+    fn transform(&self, color: Color) -> Color {
+        self.iter()
+            .fold(color, |color, transform| transform.transform(color))
+    }
+}
+
+/// Built-in color transforms, stored in recipes. Amounts go from 0 (no
+/// change) to 1.
+#[derive(PartialEq, Clone, Copy, Serialize, Deserialize, Debug)]
+#[serde(tag = "filter", rename_all = "snake_case")]
+pub enum Filter {
+    /// Lighter and less saturated
+    Pastel {
+        amount: f32,
+    },
+    /// Fully saturated and bright
+    Neon {
+        amount: f32,
+    },
+    /// Every color in shades of a single hue, in degrees
+    Monochrome {
+        hue: f32,
+    },
+    Sepia {
+        amount: f32,
+    },
+    /// Dimmed so light colors don't glare on dark backgrounds
+    DarkMode {
+        amount: f32,
+    },
+    /// Lightness pushed away from the middle
+    HighContrast {
+        amount: f32,
+    },
+    /// Softens saturated colors and keeps grays away from pure black and white
+    ReduceStrain,
+}
+
+impl Filter {
+    pub const ALL: [Filter; 7] = [
+        Filter::Pastel { amount: 0.5 },
+        Filter::Neon { amount: 0.5 },
+        Filter::Monochrome { hue: 270. },
+        Filter::Sepia { amount: 1. },
+        Filter::DarkMode { amount: 0.5 },
+        Filter::HighContrast { amount: 0.5 },
+        Filter::ReduceStrain,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Filter::Pastel { .. } => "Pastel",
+            Filter::Neon { .. } => "Neon",
+            Filter::Monochrome { .. } => "Monochrome",
+            Filter::Sepia { .. } => "Sepia",
+            Filter::DarkMode { .. } => "Dark Mode",
+            Filter::HighContrast { .. } => "High Contrast",
+            Filter::ReduceStrain => "Reduce Eye Strain",
+        }
+    }
+
+    /// Current value and maximum of the filter's parameter, if it has one.
+    pub fn parameter(&self) -> Option<(f32, f32)> {
+        match *self {
+            Filter::Pastel { amount }
+            | Filter::Neon { amount }
+            | Filter::Sepia { amount }
+            | Filter::DarkMode { amount }
+            | Filter::HighContrast { amount } => Some((amount, 1.)),
+            Filter::Monochrome { hue } => Some((hue, 360.)),
+            Filter::ReduceStrain => None,
+        }
+    }
+
+    pub fn set_parameter(&mut self, value: f32) {
+        match self {
+            Filter::Pastel { amount }
+            | Filter::Neon { amount }
+            | Filter::Sepia { amount }
+            | Filter::DarkMode { amount }
+            | Filter::HighContrast { amount } => *amount = value.clamp(0., 1.),
+            Filter::Monochrome { hue } => *hue = value.rem_euclid(360.),
+            Filter::ReduceStrain => {}
+        }
+    }
+}
+
+impl ColorTransform for Filter {
+    fn transform(&self, color: Color) -> Color {
+        let (h, s, l) = color.to_hsl();
+        match *self {
+            Filter::Pastel { amount } => {
+                color.with_hsl((h, s * (1. - amount / 2.), l + (1. - l) * amount / 2.))
+            }
+            Filter::Neon { amount } => {
+                // Grays have no hue to saturate
+                let s = if s > 0. { s + (1. - s) * amount } else { s };
+                color.with_hsl((h, s, l + (0.55 - l) * amount / 2.))
+            }
+            Filter::Monochrome { hue } => {
+                // Perceived lightness, so that yellow stays lighter than blue
+                color.with_hsl((hue, 0.6, color.to_oklab().l))
+            }
+            Filter::Sepia { amount } => {
+                let (r, g, b) = color.to_rgb();
+                let sepia = (
+                    0.393 * r + 0.769 * g + 0.189 * b,
+                    0.349 * r + 0.686 * g + 0.168 * b,
+                    0.272 * r + 0.534 * g + 0.131 * b,
+                );
+                let mix = |from: f32, to: f32| from + (to - from) * amount;
+                color.with_rgb((mix(r, sepia.0), mix(g, sepia.1), mix(b, sepia.2)))
+            }
+            Filter::DarkMode { amount } => color.with_hsl((h, s, l * (1. - amount * 0.4))),
+            Filter::HighContrast { amount } => color.with_hsl((
+                h,
+                s + (1. - s) * amount / 2.,
+                0.5 + (l - 0.5) * (1. + amount),
+            )),
+            Filter::ReduceStrain => reduce_strain(color),
+        }
+    }
+}
+
+fn reduce_strain(color: Color) -> Color {
+    let lch = color.to_oklch();
+
+    // Near-grays only keep away from pure black and white
+    if lch.c < 0.02 {
+        return color.with_oklch(Oklch {
+            l: lch.l.clamp(0.32, 0.88),
+            c: 0.,
+            ..lch
+        });
+    }
+
+    // Softening adjustments, the hue stays as perceived
+    color.with_oklch(Oklch {
+        l: lch.l.clamp(0.45, 0.85),
+        c: lch.c.clamp(0.06, 0.13),
+        ..lch
+    })
+}
@@ -0,0 +1,83 @@
+use super::FlagData;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+/// How the name of a mix is put together from the names of its flags.
+#[derive(PartialEq, Eq, Clone, Copy, Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum NameTemplate {
+    /// "Bisexual Lesbian"
+    #[default]
+    Plain,
+    /// "Bisexual + Lesbian"
+    Plus,
+    /// "Bi Lesbian"
+    Adjective,
+    /// "Bilesbian"
+    Portmanteau,
+}
+
+impl NameTemplate {
+    pub const ALL: [NameTemplate; 4] = [
+        NameTemplate::Plain,
+        NameTemplate::Plus,
+        NameTemplate::Adjective,
+        NameTemplate::Portmanteau,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            NameTemplate::Plain => "A B",
+            NameTemplate::Plus => "A + B",
+            NameTemplate::Adjective => "Adjective",
+            NameTemplate::Portmanteau => "Portmanteau",
+        }
+    }
+
+    /// Names a mix of `flags`, in mixing order.
+    pub fn apply(&self, flags: &[&FlagData]) -> String {
+        // Repeated names and names already contained in a longer one add
+        // nothing, like Gay in Gayromantic
+        let flags: Vec<&FlagData> = flags
+            .iter()
+            .unique_by(|flag| flag.name.to_lowercase())
+            .filter(|flag| {
+                let name = flag.name.to_lowercase();
+                !flags.iter().any(|other| {
+                    other.name.len() > flag.name.len()
+                        && other.name.to_lowercase().starts_with(&name)
+                })
+            })
+            .copied()
+            .collect();
+        let Some((last, rest)) = flags.split_last() else {
+            return String::new();
+        };
+
+        match self {
+            NameTemplate::Plain => flags.iter().map(|flag| flag.name.as_str()).join(" "),
+            NameTemplate::Plus => flags.iter().map(|flag| flag.name.as_str()).join(" + "),
+            NameTemplate::Adjective => rest
+                .iter()
+                .map(|flag| flag.adjective.as_str())
+                .chain([last.name.as_str()])
+                .join(" "),
+            NameTemplate::Portmanteau => {
+                let prefix: String = rest.iter().map(|flag| flag.prefix.as_str()).collect();
+                if prefix.is_empty() {
+                    last.name.clone()
+                } else {
+                    prefix + &last.name.to_lowercase()
+                }
+            }
+        }
+    }
+}
+
+/// Drops a variant suffix like " (7 stripes)" from a flag name.
+pub fn strip_variant(name: &str) -> &str {
+    match name.rfind(" (") {
+        Some(i) if name.ends_with(')') => &name[..i],
+        _ => name,
+    }
+}
@@ -0,0 +1,276 @@
+use super::{Color, FLAG_HEIGHT, FLAG_WIDTH, FlagData, Geometry, Orientation, Position, Transform};
+use serde::{Deserialize, Serialize};
+
+/// Tweak applied to a flag after it has been created. Operations compose, so
+/// a list of them can be replayed on top of a mix.
+#[derive(PartialEq, Eq, Clone, Serialize, Deserialize, Debug)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Operation {
+    /// Stripes in reverse order
+    Reverse,
+    /// Flip from left to right. Mixed flags swap their regions.
+    Mirror,
+    /// Turn a quarter clockwise, stripes becoming bands and the other way round
+    Rotate,
+    InsertStripe {
+        index: usize,
+        color: Color,
+    },
+    RemoveStripe {
+        index: usize,
+    },
+    RecolorStripe {
+        index: usize,
+        color: Color,
+    },
+    /// Recolor the part of a stripe from the `column`-th mixed flag
+    RecolorCell {
+        index: usize,
+        column: usize,
+        color: Color,
+    },
+}
+
+impl Operation {
+    /// Whether the operation changes the flag. Mixes laid out in regions
+    /// can't be mirrored or rotated, their layouts only go one way.
+    pub fn applies_to(&self, flag: &FlagData) -> bool {
+        match self {
+            Operation::Mirror | Operation::Rotate => {
+                flag.source_count() == 1 || !flag.mode.has_regions()
+            }
+            _ => true,
+        }
+    }
+
+    /// Whether the operation points at stripes by index, which only hold for
+    /// the mix it was made on. Whole-flag operations fit any mix.
+    pub fn is_indexed(&self) -> bool {
+        matches!(
+            self,
+            Operation::InsertStripe { .. }
+                | Operation::RemoveStripe { .. }
+                | Operation::RecolorStripe { .. }
+                | Operation::RecolorCell { .. }
+        )
+    }
+
+    pub fn apply(&self, flag: &FlagData) -> FlagData {
+        if !self.applies_to(flag) {
+            return flag.clone();
+        }
+        match self {
+            Operation::Reverse => reverse(flag),
+            Operation::Mirror => {
+                let mirrored = match flag.orientation {
+                    Orientation::Horizontal => mirror_columns(flag),
+                    Orientation::Vertical => reverse(flag),
+                };
+                map_placement(&mirrored, mirror_transform, mirror_geometry)
+            }
+            Operation::Rotate => {
+                let rotated = match flag.orientation {
+                    // The top stripe ends up on the right
+                    Orientation::Horizontal => FlagData {
+                        orientation: Orientation::Vertical,
+                        ..reverse(flag)
+                    },
+                    // The hoist band ends up on top, whatever was on top goes right
+                    Orientation::Vertical => FlagData {
+                        orientation: Orientation::Horizontal,
+                        ..mirror_columns(flag)
+                    },
+                };
+                map_placement(&rotated, rotate_transform, rotate_geometry)
+            }
+            Operation::InsertStripe { index, color } => {
+                let mut flag = flag.clone();
+                let index = (*index).min(flag.lines.len());
+                let width = flag.lines.first().map_or(1, Vec::len);
+                let height = flag.heights[index.min(flag.heights.len() - 1)];
+                flag.lines.insert(index, vec![*color; width]);
+                flag.heights.insert(index, height);
+                flag
+            }
+            Operation::RemoveStripe { index } => {
+                let mut flag = flag.clone();
+                // Keep at least one stripe to draw
+                if *index < flag.lines.len() && flag.lines.len() > 1 {
+                    flag.lines.remove(*index);
+                    flag.heights.remove(*index);
+                }
+                flag
+            }
+            Operation::RecolorStripe { index, color } => {
+                let mut flag = flag.clone();
+                if let Some(line) = flag.lines.get_mut(*index) {
+                    line.fill(*color);
+                }
+                flag
+            }
+            Operation::RecolorCell {
+                index,
+                column,
+                color,
+            } => {
+                let mut flag = flag.clone();
+                if let Some(cell) = flag
+                    .lines
+                    .get_mut(*index)
+                    .and_then(|line| line.get_mut(*column))
+                {
+                    *cell = *color;
+                }
+                flag
+            }
+        }
+    }
+
+    /// Applies `operations` one after another. The flag is normalized before
+    /// every operation, so stripes are counted the way they are drawn.
+    pub fn apply_all(flag: &FlagData, operations: &[Operation]) -> FlagData {
+        operations
+            .iter()
+            .fold(FlagData::normalize(flag), |flag, operation| {
+                FlagData::normalize(&operation.apply(&flag))
+            })
+    }
+}
+
+fn reverse(flag: &FlagData) -> FlagData {
+    FlagData {
+        lines: flag.lines.iter().rev().cloned().collect(),
+        heights: flag.heights.iter().rev().copied().collect(),
+        ..flag.clone()
+    }
+}
+
+/// Reverses the colors of every line, along with the regions of mixed flags.
+fn mirror_columns(flag: &FlagData) -> FlagData {
+    let position = |position: &Position| match position {
+        Position::Single => Position::Single,
+        Position::Merged { index, count } => Position::Merged {
+            index: count - 1 - index,
+            count: *count,
+        },
+    };
+
+    FlagData {
+        lines: flag
+            .lines
+            .iter()
+            .map(|line| line.iter().rev().cloned().collect())
+            .collect(),
+        weights: flag.weights.iter().rev().copied().collect(),
+        symbols: flag
+            .symbols
+            .iter()
+            .map(|(symbol, p)| (symbol.clone(), position(p)))
+            .collect(),
+        shapes: flag
+            .shapes
+            .iter()
+            .map(|(shape, p)| (shape.clone(), position(p)))
+            .collect(),
+        ..flag.clone()
+    }
+}
+
+/// Moves the symbols and shapes of a single flag along with its stripes.
+/// Mixed flags are laid out by their mode, which the renderer transposes for
+/// vertical mixes.
+fn map_placement(
+    flag: &FlagData,
+    transform: fn(&Transform) -> Transform,
+    geometry: fn(&Geometry) -> Geometry,
+) -> FlagData {
+    if flag.source_count() > 1 {
+        return flag.clone();
+    }
+    FlagData {
+        symbols: flag
+            .symbols
+            .iter()
+            .map(|(symbol, p)| {
+                let mut symbol = symbol.clone();
+                symbol.single = transform(&symbol.single);
+                (symbol, p.clone())
+            })
+            .collect(),
+        shapes: flag
+            .shapes
+            .iter()
+            .map(|(shape, p)| {
+                let mut shape = shape.clone();
+                shape.geometry = geometry(&shape.geometry);
+                (shape, p.clone())
+            })
+            .collect(),
+        ..flag.clone()
+    }
+}
+
+fn mirror_transform(t: &Transform) -> Transform {
+    Transform {
+        x: FLAG_WIDTH - t.x - t.width,
+        ..t.clone()
+    }
+}
+
+/// Symbols stay upright, only their center is moved.
+fn rotate_transform(t: &Transform) -> Transform {
+    let (cx, cy) = rotate_point(&(t.x + t.width / 2, t.y + t.height / 2));
+    Transform {
+        x: cx - t.width / 2,
+        y: cy - t.height / 2,
+        ..t.clone()
+    }
+}
+
+/// Quarter turn clockwise, stretched back onto the flag's proportions.
+fn rotate_point((x, y): &(i32, i32)) -> (i32, i32) {
+    (
+        FLAG_WIDTH - y * FLAG_WIDTH / FLAG_HEIGHT,
+        x * FLAG_HEIGHT / FLAG_WIDTH,
+    )
+}
+
+fn mirror_geometry(geometry: &Geometry) -> Geometry {
+    map_geometry(geometry, |(x, y)| (FLAG_WIDTH - x, *y))
+}
+
+fn rotate_geometry(geometry: &Geometry) -> Geometry {
+    map_geometry(geometry, rotate_point)
+}
+
+/// Moves every point of `geometry`. Circles can't be stretched, so they only
+/// move their center.
+fn map_geometry(geometry: &Geometry, f: impl Fn(&(i32, i32)) -> (i32, i32)) -> Geometry {
+    match geometry {
+        Geometry::Rect {
+            x,
+            y,
+            width,
+            height,
+        } => {
+            let (x1, y1) = f(&(*x, *y));
+            let (x2, y2) = f(&(x + width, y + height));
+            Geometry::Rect {
+                x: x1.min(x2),
+                y: y1.min(y2),
+                width: (x2 - x1).abs(),
+                height: (y2 - y1).abs(),
+            }
+        }
+        Geometry::Triangle { points } => Geometry::Triangle {
+            points: points.map(|p| f(&p)),
+        },
+        Geometry::Circle { cx, cy, r } => {
+            let (cx, cy) = f(&(*cx, *cy));
+            Geometry::Circle { cx, cy, r: *r }
+        }
+        Geometry::Polygon { points } => Geometry::Polygon {
+            points: points.iter().map(f).collect(),
+        },
+    }
+}
@@ -0,0 +1,183 @@
+use super::{Color, FlagData};
+use itertools::Itertools;
+use serde::Serialize;
+
+/// File formats the colors of a flag can be exported to.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum PaletteFormat {
+    /// GIMP and Inkscape palette
+    #[default]
+    Gpl,
+    /// Adobe Swatch Exchange
+    Ase,
+    /// Custom properties on `:root`
+    Css,
+    Scss,
+    Json,
+}
+
+impl PaletteFormat {
+    pub const ALL: [PaletteFormat; 5] = [
+        PaletteFormat::Gpl,
+        PaletteFormat::Ase,
+        PaletteFormat::Css,
+        PaletteFormat::Scss,
+        PaletteFormat::Json,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PaletteFormat::Gpl => "GIMP (.gpl)",
+            PaletteFormat::Ase => "Adobe (.ase)",
+            PaletteFormat::Css => "CSS",
+            PaletteFormat::Scss => "SCSS",
+            PaletteFormat::Json => "JSON",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            PaletteFormat::Gpl => "gpl",
+            PaletteFormat::Ase => "ase",
+            PaletteFormat::Css => "css",
+            PaletteFormat::Scss => "scss",
+            PaletteFormat::Json => "json",
+        }
+    }
+
+    pub fn mime(&self) -> &'static str {
+        match self {
+            PaletteFormat::Gpl => "text/plain",
+            PaletteFormat::Ase => "application/octet-stream",
+            PaletteFormat::Css => "text/css",
+            PaletteFormat::Scss => "text/x-scss",
+            PaletteFormat::Json => "application/json",
+        }
+    }
+}
+
+/// Distinct colors of a flag, in the order they appear.
+#[derive(PartialEq, Clone, Serialize, Debug)]
+pub struct Palette {
+    pub name: String,
+    pub colors: Vec<PaletteColor>,
+}
+
+#[derive(PartialEq, Clone, Serialize, Debug)]
+pub struct PaletteColor {
+    pub name: String,
+    #[serde(rename = "hex")]
+    pub color: Color,
+}
+
+impl Palette {
+    /// Stripes come first, top to bottom and then left to right within each
+    /// stripe, followed by the colors of shapes.
+    pub fn new(flag: &FlagData) -> Palette {
+        let shapes = flag
+            .shapes
+            .iter()
+            .flat_map(|(shape, _)| [shape.fill, shape.stroke])
+            .flatten();
+        let colors = flag
+            .lines
+            .iter()
+            .flatten()
+            .copied()
+            .chain(shapes)
+            .unique()
+            .enumerate()
+            .map(|(i, color)| PaletteColor {
+                name: format!("Color {}", i + 1),
+                color,
+            })
+            .collect();
+        Palette {
+            name: flag.name.clone(),
+            colors,
+        }
+    }
+
+    /// Name of the palette usable in file and variable names, like "trans-gay".
+    pub fn slug(&self) -> String {
+        let slug = self
+            .name
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(str::to_ascii_lowercase)
+            .join("-");
+        if slug.is_empty() {
+            "flag".to_string()
+        } else {
+            slug
+        }
+    }
+
+    pub fn export(&self, format: PaletteFormat) -> Vec<u8> {
+        let slug = self.slug();
+        let variable = |i: usize| format!("{slug}-{}", i + 1);
+        match format {
+            PaletteFormat::Gpl => {
+                let mut lines = vec![
+                    "GIMP Palette".to_string(),
+                    format!("Name: {}", self.name),
+                    format!("Columns: {}", self.colors.len()),
+                    "#".to_string(),
+                ];
+                lines.extend(self.colors.iter().map(|entry| {
+                    let Color { r, g, b, .. } = entry.color;
+                    format!("{r:3} {g:3} {b:3}\t{}", entry.name)
+                }));
+                (lines.join("\n") + "\n").into_bytes()
+            }
+            PaletteFormat::Ase => self.to_ase(),
+            PaletteFormat::Css => {
+                let properties = self
+                    .colors
+                    .iter()
+                    .enumerate()
+                    .map(|(i, entry)| format!("  --{}: {};\n", variable(i), entry.color))
+                    .join("");
+                format!(":root {{\n{properties}}}\n").into_bytes()
+            }
+            PaletteFormat::Scss => self
+                .colors
+                .iter()
+                .enumerate()
+                .map(|(i, entry)| format!("${}: {};\n", variable(i), entry.color))
+                .join("")
+                .into_bytes(),
+            PaletteFormat::Json => {
+                serde_json::to_vec_pretty(self).expect("Palettes always serialize")
+            }
+        }
+    }
+
+    /// Adobe Swatch Exchange 1.0: a header and one RGB block per color, all
+    /// big-endian with UTF-16 names.
+    fn to_ase(&self) -> Vec<u8> {
+        let mut bytes = b"ASEF".to_vec();
+        bytes.extend(1u16.to_be_bytes());
+        bytes.extend(0u16.to_be_bytes());
+        bytes.extend((self.colors.len() as u32).to_be_bytes());
+
+        for entry in &self.colors {
+            let name: Vec<u16> = entry.name.encode_utf16().chain([0]).collect();
+            let mut block = (name.len() as u16).to_be_bytes().to_vec();
+            block.extend(name.iter().flat_map(|unit| unit.to_be_bytes()));
+            block.extend(b"RGB ");
+            let (r, g, b) = entry.color.to_rgb();
+            for channel in [r, g, b] {
+                block.extend(channel.to_be_bytes());
+            }
+            // Normal color, as opposed to global or spot ones
+            block.extend(2u16.to_be_bytes());
+
+            // Color entry
+            bytes.extend(1u16.to_be_bytes());
+            bytes.extend((block.len() as u32).to_be_bytes());
+            bytes.extend(block);
+        }
+        bytes
+    }
+}
@@ -0,0 +1,134 @@
+use super::{Filter, FlagData, MixOptions, Operation, Vision};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::fmt;
+
+/// Bumped whenever a recipe saved by an older version would not rebuild the
+/// same flag anymore.
+pub const RECIPE_VERSION: u32 = 2;
+
+/// Options of the `Flag` component that change how a flag looks.
+#[derive(PartialEq, Clone, Serialize, Deserialize, Debug)]
+#[serde(default)]
+pub struct RenderOptions {
+    pub icons: bool,
+    /// Applied to every color in order
+    pub filters: Vec<Filter>,
+    pub softness: f32,
+    pub blur: f32,
+    pub vision: Vision,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            icons: true,
+            filters: vec![],
+            softness: 40.0,
+            blur: 0.0,
+            vision: Vision::Normal,
+        }
+    }
+}
+
+/// Everything needed to rebuild a mix: which flags went in, how they were
+/// mixed, how the result was edited afterwards and how it was rendered.
+#[derive(PartialEq, Clone, Serialize, Deserialize, Debug)]
+pub struct Recipe {
+    pub version: u32,
+    /// Ids of the mixed flags, in mixing order
+    pub flags: Vec<String>,
+    #[serde(default)]
+    pub mix: MixOptions,
+    /// Applied to the mix in order
+    #[serde(default)]
+    pub ops: Vec<Operation>,
+    #[serde(default)]
+    pub render: RenderOptions,
+}
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum RecipeError {
+    Json(String),
+    UnsupportedVersion(u32),
+    UnknownFlag(String),
+    NoFlags,
+}
+
+impl fmt::Display for RecipeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RecipeError::Json(e) => write!(f, "Invalid recipe: {e}"),
+            RecipeError::UnsupportedVersion(v) => {
+                write!(
+                    f,
+                    "Recipe version {v} is not supported (expected {RECIPE_VERSION})"
+                )
+            }
+            RecipeError::UnknownFlag(id) => write!(f, "Unknown flag \"{id}\""),
+            RecipeError::NoFlags => write!(f, "The recipe has no flags"),
+        }
+    }
+}
+
+impl Recipe {
+    pub fn new(
+        flags: &[FlagData],
+        mix: MixOptions,
+        ops: Vec<Operation>,
+        render: RenderOptions,
+    ) -> Recipe {
+        Recipe {
+            version: RECIPE_VERSION,
+            flags: flags.iter().map(|flag| flag.id.clone()).collect(),
+            mix,
+            ops,
+            render,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("Recipes always serialize")
+    }
+
+    pub fn from_json(json: &str) -> Result<Recipe, RecipeError> {
+        let mut value: Value =
+            serde_json::from_str(json).map_err(|e| RecipeError::Json(e.to_string()))?;
+        if let Some(fields) = value.as_object_mut()
+            && fields.get("version") == Some(&json!(1))
+        {
+            // Version 1 had a strain reduction toggle instead of filters
+            if let Some(render) = fields.get_mut("render").and_then(Value::as_object_mut)
+                && render.remove("reduce_strain") == Some(json!(true))
+            {
+                render.insert("filters".to_string(), json!([Filter::ReduceStrain]));
+            }
+            fields.insert("version".to_string(), json!(RECIPE_VERSION));
+        }
+        let recipe: Recipe =
+            serde_json::from_value(value).map_err(|e| RecipeError::Json(e.to_string()))?;
+        if recipe.version != RECIPE_VERSION {
+            return Err(RecipeError::UnsupportedVersion(recipe.version));
+        }
+        Ok(recipe)
+    }
+
+    /// Looks the source flags up in `catalog`, in recipe order. Mixing them
+    /// with the recipe's `mix` options and applying its `ops` to the mix
+    /// rebuilds the saved flag.
+    pub fn sources(&self, catalog: &[FlagData]) -> Result<Vec<FlagData>, RecipeError> {
+        if self.flags.is_empty() {
+            return Err(RecipeError::NoFlags);
+        }
+        self.flags
+            .iter()
+            .map(|id| {
+                catalog
+                    .iter()
+                    .find(|flag| &flag.id == id)
+                    .cloned()
+                    .ok_or_else(|| RecipeError::UnknownFlag(id.clone()))
+            })
+            .collect()
+    }
+}
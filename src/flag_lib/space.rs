@@ -0,0 +1,207 @@
+/// sRGB channels between 0 and 1 without the gamma, proportional to light.
+/// Every other space converts from and to it.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct LinearRgb {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+}
+
+/// Perceptual lightness, green-red and blue-yellow axes, where equal
+/// distances look about equally different.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct Oklab {
+    pub l: f32,
+    pub a: f32,
+    pub b: f32,
+}
+
+/// OKLab in polar form: lightness, chroma and hue in degrees.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct Oklch {
+    pub l: f32,
+    pub c: f32,
+    pub h: f32,
+}
+
+/// CIELAB under the D65 illuminant, lightness going from 0 to 100.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct Lab {
+    pub l: f32,
+    pub a: f32,
+    pub b: f32,
+}
+
+// Chroma under which a color has no meaningful hue
+const ACHROMATIC: f32 = 0.002;
+
+impl LinearRgb {
+    /// Removes the gamma of sRGB channels between 0 and 1.
+    pub fn from_srgb((r, g, b): (f32, f32, f32)) -> LinearRgb {
+        let linear = |v: f32| {
+            if v <= 0.04045 {
+                v / 12.92
+            } else {
+                ((v + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        LinearRgb {
+            r: linear(r),
+            g: linear(g),
+            b: linear(b),
+        }
+    }
+
+    /// sRGB channels, out of gamut ones are left outside of 0 and 1.
+    pub fn to_srgb(self) -> (f32, f32, f32) {
+        let gamma = |v: f32| {
+            if v.abs() <= 0.003_130_8 {
+                v * 12.92
+            } else {
+                (1.055 * v.abs().powf(1. / 2.4) - 0.055).copysign(v)
+            }
+        };
+        (gamma(self.r), gamma(self.g), gamma(self.b))
+    }
+
+    /// Whether the color can be shown on an sRGB screen, rounding aside.
+    pub fn in_gamut(&self) -> bool {
+        [self.r, self.g, self.b]
+            .iter()
+            .all(|v| (-1e-4..=1. + 1e-4).contains(v))
+    }
+}
+
+impl From<LinearRgb> for Oklab {
+    fn from(LinearRgb { r, g, b }: LinearRgb) -> Oklab {
+        let l = (0.412_221_46 * r + 0.536_332_55 * g + 0.051_445_995 * b).cbrt();
+        let m = (0.211_903_5 * r + 0.680_699_5 * g + 0.107_396_96 * b).cbrt();
+        let s = (0.088_302_46 * r + 0.281_718_85 * g + 0.629_978_7 * b).cbrt();
+
+        Oklab {
+            l: 0.210_454_26 * l + 0.793_617_8 * m - 0.004_072_047 * s,
+            a: 1.977_998_5 * l - 2.428_592_2 * m + 0.450_593_7 * s,
+            b: 0.025_904_037 * l + 0.782_771_77 * m - 0.808_675_77 * s,
+        }
+    }
+}
+
+impl From<Oklab> for LinearRgb {
+    fn from(Oklab { l, a, b }: Oklab) -> LinearRgb {
+        let l_ = (l + 0.396_337_78 * a + 0.215_803_76 * b).powi(3);
+        let m_ = (l - 0.105_561_346 * a - 0.063_854_17 * b).powi(3);
+        let s_ = (l - 0.089_484_18 * a - 1.291_485_5 * b).powi(3);
+
+        LinearRgb {
+            r: 4.076_741_7 * l_ - 3.307_711_6 * m_ + 0.230_969_94 * s_,
+            g: -1.268_438 * l_ + 2.609_757_4 * m_ - 0.341_319_38 * s_,
+            b: -0.004_196_086_3 * l_ - 0.703_418_6 * m_ + 1.707_614_7 * s_,
+        }
+    }
+}
+
+impl Oklab {
+    /// Euclidean distance, about 0.02 for colors that are hard to tell apart
+    /// and 1 between black and white.
+    pub fn distance(&self, other: &Oklab) -> f32 {
+        ((self.l - other.l).powi(2) + (self.a - other.a).powi(2) + (self.b - other.b).powi(2))
+            .sqrt()
+    }
+}
+
+impl From<Oklab> for Oklch {
+    fn from(Oklab { l, a, b }: Oklab) -> Oklch {
+        Oklch {
+            l,
+            c: a.hypot(b),
+            h: b.atan2(a).to_degrees().rem_euclid(360.),
+        }
+    }
+}
+
+impl From<Oklch> for Oklab {
+    fn from(Oklch { l, c, h }: Oklch) -> Oklab {
+        let (sin, cos) = h.to_radians().sin_cos();
+        Oklab {
+            l,
+            a: c * cos,
+            b: c * sin,
+        }
+    }
+}
+
+impl Oklch {
+    /// The closest color an sRGB screen can show, keeping lightness and hue
+    /// and only giving up chroma.
+    pub fn to_gamut(self) -> LinearRgb {
+        let l = self.l.clamp(0., 1.);
+        let linear = |c: f32| LinearRgb::from(Oklab::from(Oklch { l, c, ..self }));
+        let exact = linear(self.c);
+        if exact.in_gamut() {
+            return exact;
+        }
+        // Binary search on chroma, pure grays are always in gamut
+        let (mut low, mut high) = (0., self.c);
+        for _ in 0..16 {
+            let mid = (low + high) / 2.;
+            if linear(mid).in_gamut() {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+        linear(low)
+    }
+
+    /// The color `t` of the way to `other`, going the short way around the hue
+    /// circle. Chroma is interpolated rather than the a and b axes, so that
+    /// complementary colors don't meet in a muddy gray.
+    pub fn interpolate(self, other: Oklch, t: f32) -> Oklch {
+        let lerp = |from: f32, to: f32| from + (to - from) * t;
+        // Grays take the hue of the other color instead of sweeping through
+        // every hue in between
+        let (h1, h2) = match (self.c < ACHROMATIC, other.c < ACHROMATIC) {
+            (true, false) => (other.h, other.h),
+            (false, true) => (self.h, self.h),
+            _ => (self.h, other.h),
+        };
+        let turn = (h2 - h1 + 180.).rem_euclid(360.) - 180.;
+        Oklch {
+            l: lerp(self.l, other.l),
+            c: lerp(self.c, other.c),
+            h: (h1 + turn * t).rem_euclid(360.),
+        }
+    }
+}
+
+impl From<LinearRgb> for Lab {
+    fn from(LinearRgb { r, g, b }: LinearRgb) -> Lab {
+        // Relative to the D65 white point
+        let x = (0.412_456_4 * r + 0.357_576_1 * g + 0.180_437_5 * b) / 0.950_47;
+        let y = 0.212_672_9 * r + 0.715_152_2 * g + 0.072_175 * b;
+        let z = (0.019_333_9 * r + 0.119_192 * g + 0.950_304_1 * b) / 1.088_83;
+
+        let delta: f32 = 6. / 29.;
+        let f = |t: f32| {
+            if t > delta.powi(3) {
+                t.cbrt()
+            } else {
+                t / (3. * delta * delta) + 4. / 29.
+            }
+        };
+        Lab {
+            l: 116. * f(y) - 16.,
+            a: 500. * (f(x) - f(y)),
+            b: 200. * (f(y) - f(z)),
+        }
+    }
+}
+
+impl Lab {
+    /// CIE76 color difference, about 2.3 for colors that are hard to tell
+    /// apart.
+    pub fn delta_e(&self, other: &Lab) -> f32 {
+        ((self.l - other.l).powi(2) + (self.a - other.a).powi(2) + (self.b - other.b).powi(2))
+            .sqrt()
+    }
+}
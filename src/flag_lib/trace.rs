@@ -0,0 +1,85 @@
+use super::{Alignment, Parity};
+
+/// Index of a mixed flag and of one of its original stripes, none for
+/// stripes added to even it out
+pub type Origin = (usize, Option<usize>);
+
+/// How one of the mixed flags was brought to the common stripe count.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct SourceTrace {
+    pub name: String,
+    /// Stripe count of the flag before mixing
+    pub stripes: usize,
+    /// Strategy used to match the parity of the other flags, if needed
+    pub parity: Option<Parity>,
+    /// How many times every stripe was repeated to reach the common height
+    pub factor: usize,
+}
+
+impl SourceTrace {
+    pub fn describe(&self, alignment: Option<Alignment>, bands: usize) -> String {
+        let mut steps = vec![format!("{} stripes", self.stripes)];
+        match self.parity {
+            Some(Parity::DuplicateMiddle) => {
+                steps.push(format!("middle stripe {} duplicated", self.stripes / 2 + 1))
+            }
+            Some(Parity::DuplicateEdges) => steps.push("edge stripes stretched".to_string()),
+            Some(Parity::Separator) => steps.push("separator inserted".to_string()),
+            Some(Parity::Proportional) => steps.push("every stripe split in two".to_string()),
+            None => {}
+        }
+        match alignment {
+            Some(Alignment::Lcm) if self.factor > 1 => {
+                steps.push(format!("every stripe repeated {}×", self.factor))
+            }
+            Some(Alignment::Lcm) | None => {}
+            Some(Alignment::Resample) => steps.push(format!("resampled onto {bands} bands")),
+        }
+        format!("{}: {}", self.name, steps.join(", "))
+    }
+}
+
+/// Where every line of a mix came from, as returned by `FlagData::mix_traced`.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct MixTrace {
+    /// None when the mix keeps the original stripes, like `MixMode::Interleave`
+    pub alignment: Option<Alignment>,
+    pub sources: Vec<SourceTrace>,
+    /// Number of aligned bands every flag was cut into
+    pub bands: usize,
+    /// Where every color of every line of the mix came from
+    pub origins: Vec<Vec<Origin>>,
+}
+
+impl MixTrace {
+    /// Short label of an origin, like "B3" for the third stripe of the second
+    /// flag. Stripes stretched to even out the flag are starred, added ones
+    /// are dashed.
+    pub fn label(&self, (source, stripe): Origin) -> String {
+        let letter = (b'A' + source as u8) as char;
+        let trace = &self.sources[source];
+        match stripe {
+            Some(stripe) => {
+                let stretched = match trace.parity {
+                    Some(Parity::DuplicateMiddle) => stripe == trace.stripes / 2,
+                    Some(Parity::DuplicateEdges) => stripe == 0 || stripe + 1 == trace.stripes,
+                    _ => false,
+                };
+                format!("{letter}{}{}", stripe + 1, if stretched { "*" } else { "" })
+            }
+            None => format!("{letter}–"),
+        }
+    }
+
+    /// One line per mixed flag explaining how it was aligned.
+    pub fn describe(&self) -> Vec<String> {
+        self.sources
+            .iter()
+            .enumerate()
+            .map(|(i, source)| {
+                let letter = (b'A' + i as u8) as char;
+                format!("{letter} · {}", source.describe(self.alignment, self.bands))
+            })
+            .collect()
+    }
+}
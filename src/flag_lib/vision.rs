@@ -0,0 +1,74 @@
+use super::{Color, ColorTransform, space::LinearRgb};
+use serde::{Deserialize, Serialize};
+
+/// Color vision deficiency simulated when drawing flags, to check that mixes
+/// stay readable for colorblind viewers.
+#[derive(PartialEq, Eq, Clone, Copy, Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Vision {
+    #[default]
+    Normal,
+    /// No red cones
+    Protanopia,
+    /// No green cones
+    Deuteranopia,
+    /// No blue cones
+    Tritanopia,
+    /// No color at all, only luminance
+    Achromatopsia,
+}
+
+impl Vision {
+    pub const ALL: [Vision; 5] = [
+        Vision::Normal,
+        Vision::Protanopia,
+        Vision::Deuteranopia,
+        Vision::Tritanopia,
+        Vision::Achromatopsia,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Vision::Normal => "Normal Vision",
+            Vision::Protanopia => "Protanopia",
+            Vision::Deuteranopia => "Deuteranopia",
+            Vision::Tritanopia => "Tritanopia",
+            Vision::Achromatopsia => "Achromatopsia",
+        }
+    }
+
+    /// Matrix applied to linear RGB, from Machado, Oliveira and Fernandes
+    /// (2009) at full severity.
+    pub fn matrix(&self) -> [[f32; 3]; 3] {
+        match self {
+            Vision::Normal => [[1., 0., 0.], [0., 1., 0.], [0., 0., 1.]],
+            Vision::Protanopia => [
+                [0.152_286, 1.052_583, -0.204_868],
+                [0.114_503, 0.786_281, 0.099_216],
+                [-0.003_882, -0.048_116, 1.051_998],
+            ],
+            Vision::Deuteranopia => [
+                [0.367_322, 0.860_646, -0.227_968],
+                [0.280_085, 0.672_501, 0.047_413],
+                [-0.011_820, 0.042_940, 0.968_881],
+            ],
+            Vision::Tritanopia => [
+                [1.255_528, -0.076_749, -0.178_779],
+                [-0.078_411, 0.930_809, 0.147_602],
+                [0.004_733, 0.691_367, 0.303_900],
+            ],
+            Vision::Achromatopsia => [[0.212_6, 0.715_2, 0.072_2]; 3],
+        }
+    }
+}
+
+/// The color as seen with this vision.
+impl ColorTransform for Vision {
+    fn transform(&self, color: Color) -> Color {
+        let LinearRgb { r, g, b } = color.to_linear();
+        let [r, g, b] = self
+            .matrix()
+            .map(|row| row[0] * r + row[1] * g + row[2] * b);
+        color.with_linear(LinearRgb { r, g, b })
+    }
+}
@@ -9,8 +9,17 @@ include!(concat!(env!("OUT_DIR"), "/assets.rs"));
 static FLAGS_JSON: LazyLock<Vec<FlagDataJSON>> = LazyLock::new(|| {
     serde_json::from_str(include_str!("../assets/flags.json")).expect("Invalid flags.json")
 });
-static FLAGS: LazyLock<Vec<FlagData>> =
-    LazyLock::new(|| FLAGS_JSON.iter().map(FlagData::from_json).collect());
+// Broken entries are logged and left out rather than taking the app down
+static FLAGS: LazyLock<Vec<FlagData>> = LazyLock::new(|| {
+    FLAGS_JSON
+        .iter()
+        .filter_map(|json| {
+            FlagData::from_json(json)
+                .inspect_err(|e| error!("Skipping flag \"{}\": {e}", json.id))
+                .ok()
+        })
+        .collect()
+});
 
 #[macro_export]
 macro_rules! get_asset {
@@ -22,6 +31,7 @@ macro_rules! get_asset {
 mod app;
 mod flag;
 mod flag_lib;
+mod reverse;
 fn main() {
     dioxus::launch(app::App);
 }
@@ -0,0 +1,128 @@
+use dioxus::prelude::*;
+
+use crate::FLAGS;
+use crate::flag::Flag;
+use crate::flag_lib::{
+    Alignment, Decomposition, FlagData, Operation, Recipe, RenderOptions, decompose, parse_stripes,
+};
+
+#[derive(Props, PartialEq, Clone)]
+pub struct ReverseMixerProps {
+    pub render: RenderOptions,
+    /// Called with the pair to load into the kitchen
+    pub onselect: EventHandler<Decomposition>,
+}
+
+/// Page looking for pairs of flags that mix into pasted stripes or a recipe.
+#[component]
+pub fn ReverseMixer(props: ReverseMixerProps) -> Element {
+    let mut input = use_signal::<String>(String::new);
+    let mut target = use_signal::<Option<FlagData>>(|| None);
+    let mut matches = use_signal::<Vec<Decomposition>>(Vec::new);
+    let mut error = use_signal::<Option<String>>(|| None);
+
+    let search = move |_| {
+        let text = input.read().trim().to_string();
+        // Recipes are JSON objects, anything else is read as stripes
+        let parsed = if text.starts_with('{') {
+            Recipe::from_json(&text)
+                .and_then(|recipe| {
+                    let mix = FlagData::mix_many(&recipe.sources(&FLAGS)?, &recipe.mix);
+                    Ok(Operation::apply_all(&mix, &recipe.ops))
+                })
+                .map_err(|e| e.to_string())
+        } else {
+            parse_stripes(&text).map_err(|e| e.to_string())
+        };
+
+        match parsed {
+            Ok(flag) => {
+                matches.set(decompose(&flag, &FLAGS));
+                target.set(Some(flag));
+                error.set(None);
+            }
+            Err(e) => {
+                matches.set(vec![]);
+                target.set(None);
+                error.set(Some(e));
+            }
+        }
+    };
+
+    let render = props.render;
+    rsx! {
+        div {
+            class: "reverse-mixer",
+            h1 { "Reverse Mixer" }
+            p { "Paste the colors of a mix, one stripe per line and a color per flag on every line, or a recipe." }
+            textarea {
+                class: "recipe",
+                placeholder: "#5BCEFA #E40303\n#F5A9B8 #FF8C00\n...",
+                value: "{input}",
+                oninput: move |event| { input.set(event.value()) },
+            }
+            div {
+                class: "input-row",
+                button {
+                    onclick: search,
+                    "Find Sources"
+                }
+            }
+            if let Some(e) = &*error.read() {
+                span { class: "recipe-error", "{e}" }
+            }
+            if let Some(flag) = &*target.read() {
+                Flag {
+                    flag: flag.clone(),
+                    id: "reverse-target".to_string(),
+                    option_icons: render.icons,
+                    option_filters: render.filters.clone(),
+                    option_blur: render.blur,
+                    option_softness: render.softness,
+                    option_vision: render.vision,
+                }
+                if matches.read().is_empty() {
+                    p { "No pair of flags mixes into these stripes." }
+                }
+            }
+            div {
+                class: "flag-list",
+                for (i, found) in matches.read().iter().cloned().enumerate() {
+                    div {
+                        key: "{i}",
+                        class: "decomposition",
+                        onclick: {
+                            let found = found.clone();
+                            move |_| props.onselect.call(found.clone())
+                        },
+                        div {
+                            class: "decomposition-flags",
+                            for (k, source) in found.sources.iter().enumerate() {
+                                Flag {
+                                    flag: source.clone(),
+                                    id: format!("reverse-{i}-{k}"),
+                                    option_icons: render.icons,
+                                    option_filters: render.filters.clone(),
+                                    option_blur: render.blur,
+                                    option_softness: render.softness,
+                                    option_vision: render.vision,
+                                }
+                            }
+                        }
+                        p { {found.sources.iter().map(|flag| flag.full_name.as_str()).collect::<Vec<_>>().join(" + ")} }
+                        p {
+                            class: "decomposition-options",
+                            {found.options.mode.label()},
+                            " · ",
+                            if found.options.alignment == Alignment::Resample {
+                                "Resampled"
+                            } else {
+                                {found.options.parity.label()}
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}